@@ -1,27 +1,204 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
+mod math;
+mod verifier;
+
 use codec::{Decode, Encode};
 use frame_support::{Parameter, RuntimeDebug};
-use sp_runtime::traits::Member;
+use sp_runtime::traits::{AtLeast32BitUnsigned, Member};
+use sp_runtime::{DispatchError, DispatchResult};
+use sp_std::vec::Vec;
+
+pub use math::{exp, exp_neg, ln, ln_2};
+pub use verifier::{
+    AlwaysAccept, AlwaysReject, ConsensusFault, ConsensusFaultType, ConsensusFaultVerifier,
+    ConsensusFaultVerifyInfo, ElectionProofVerifyInfo, FakeVerifier, ProofVerifier, SealVerifyInfo,
+    SectorInfo, WindowPoStVerifyInfo, WinningPoStVerifyInfo,
+};
+
+/// Identifier of a sector, unique per miner
+pub type SectorNumber = u64;
+
+/// Identifier of a storage deal
+pub type DealId = u64;
+
+/// Space time of deals, the sum of their piece size in bytes times their duration in blocks
+pub type DealWeight = u128;
+
+/// Number of bytes a verified client can store in verified deals
+pub type DataCap = u64;
 
 pub trait Power {
     /// AccountId type for miner
     type AccountId: Parameter + Member + Clone + Eq + PartialEq + Default;
     /// Unit of Storage Power of a miner
-    type StoragePower: Parameter + Member + Clone;
+    type StoragePower: Parameter + Member + AtLeast32BitUnsigned + Copy + Default;
     /// Libp2p PeerId
     type PeerId: Parameter + Member + AsRef<[u8]> + Clone + Send + 'static;
+    /// Block number the history of power is recorded at
+    type BlockNumber;
+
+    /// Register a miner sealing its sectors with `seal_proof` - used by miner
+    fn register_new_miner(
+        miner: &Self::AccountId,
+        seal_proof: RegisteredSealProof,
+    ) -> Option<Claim<Self::StoragePower>>;
 
-    /// Register a miner - used by miner
-    fn register_new_miner(miner: &Self::AccountId) -> Option<Claim<Self::StoragePower>>;
+    /// Removes a miner and its claim - used by miner
+    /// Returns `None` if the miner is not registered or still claims power
+    fn deregister_miner(miner: &Self::AccountId) -> Option<Claim<Self::StoragePower>>;
 
     /// Updates the claimed power for a miner, requested by miners
     /// Example: Worker recovers faulty sector and adds power back
+    /// Returns `None` if the miner is not registered or the update under/overflows
     fn update_claim(
         miner: Self::AccountId,
-        raw_bytes_delta: Self::StoragePower,
-        quality_adjusted_delta: Self::StoragePower,
+        raw_bytes_delta: PowerDelta<Self::StoragePower>,
+        quality_adjusted_delta: PowerDelta<Self::StoragePower>,
+    ) -> Option<Claim<Self::StoragePower>>;
+
+    /// Total power claimed by all miners
+    fn total_power() -> Claim<Self::StoragePower>;
+
+    /// Power claimed by a miner, `None` if the miner is not registered
+    fn claim(miner: &Self::AccountId) -> Option<Claim<Self::StoragePower>>;
+
+    /// Whether the power of a miner meets the consensus minimum to produce blocks and the miner
+    /// is not suspended
+    fn is_eligible(miner: &Self::AccountId) -> bool;
+
+    /// Whether the power of a miner at the start of `block` met the consensus minimum to produce
+    /// blocks and the miner is not suspended
+    fn is_eligible_at(miner: &Self::AccountId, block: Self::BlockNumber) -> bool;
+
+    /// Makes a miner ineligible to produce blocks before `until`, e.g. after a consensus fault
+    fn suspend_eligibility(miner: &Self::AccountId, until: Self::BlockNumber);
+
+    /// Power claimed by a miner at the start of `block`, `None` if the miner was not registered
+    /// or the block is not retained
+    fn claim_at(
+        miner: &Self::AccountId,
+        block: Self::BlockNumber,
     ) -> Option<Claim<Self::StoragePower>>;
+
+    /// Total power claimed at the start of `block`, `None` if the block is not retained
+    fn total_power_at(block: Self::BlockNumber) -> Option<Claim<Self::StoragePower>>;
+}
+
+/// Receives the block rewards of miners, implemented by the miner pallet
+pub trait RewardDistributor {
+    /// AccountId type for miner
+    type AccountId;
+    /// Currency balance the rewards are paid in
+    type Balance;
+
+    /// Called with the reward already deposited in the miner account, e.g. to lock it
+    fn distribute_reward(miner: &Self::AccountId, reward: Self::Balance);
+}
+
+/// Accounts of miners, implemented by the miner pallet
+pub trait MinerAccounts {
+    /// AccountId type for miner
+    type AccountId;
+
+    /// Owner of a miner, `None` if the account is not a miner
+    fn owner(miner: &Self::AccountId) -> Option<Self::AccountId>;
+
+    /// Worker of a miner, `None` if the account is not a miner
+    fn worker(miner: &Self::AccountId) -> Option<Self::AccountId>;
+
+    /// Whether `address` is the owner, worker or a controller of `miner`
+    fn is_controlling_address(miner: &Self::AccountId, address: &Self::AccountId) -> bool;
+}
+
+/// Sectors challenged by WinningPoSt, implemented by the miner pallet
+pub trait WinningPoStSectors {
+    /// AccountId type for miner
+    type AccountId;
+
+    /// Active sectors of `miner` the WinningPoSt submitted with an election proof drawn from
+    /// `randomness` must prove
+    fn challenged_sectors(miner: &Self::AccountId, randomness: &[u8]) -> Vec<SectorInfo>;
+}
+
+/// Storage deals activated in sectors, implemented by the market pallet
+pub trait Market {
+    /// AccountId type for miner
+    type AccountId;
+    /// Block number the deals start and end at
+    type BlockNumber;
+
+    /// Checks `deal_ids` can be activated by `miner` in a sector of `sector_size` bytes active
+    /// from `sector_activation` until `sector_expiration`, returns the weight of the deals and
+    /// the weight of the verified deals
+    fn verify_deals_for_activation(
+        miner: &Self::AccountId,
+        deal_ids: &[DealId],
+        sector_activation: Self::BlockNumber,
+        sector_expiration: Self::BlockNumber,
+        sector_size: u64,
+    ) -> Result<(DealWeight, DealWeight), DispatchError>;
+
+    /// Activates deals verified by `verify_deals_for_activation` in a sector proven at
+    /// `sector_activation`
+    fn activate_deals(deal_ids: &[DealId], sector_activation: Self::BlockNumber);
+
+    /// Marks the active deals of `miner` in sectors terminated at `epoch` to be slashed
+    fn on_sectors_terminated(
+        miner: &Self::AccountId,
+        deal_ids: &[DealId],
+        epoch: Self::BlockNumber,
+    );
+
+    /// Whether `address` has funds in escrow, locked or not, e.g. a provider that must withdraw
+    /// them before it is removed
+    fn has_escrow(address: &Self::AccountId) -> bool;
+}
+
+/// DataCap of verified clients, implemented by the verified registry pallet
+pub trait VerifiedRegistry {
+    /// AccountId type for client
+    type AccountId;
+
+    /// Checks `client` has the DataCap for verified deals of `deal_sizes` bytes
+    fn can_use_bytes(client: &Self::AccountId, deal_sizes: &[DataCap]) -> DispatchResult;
+
+    /// Consumes the DataCap of `client` for verified deals checked by `can_use_bytes`
+    fn use_bytes(client: &Self::AccountId, deal_sizes: &[DataCap]);
+
+    /// Restores the DataCap of `client` consumed by a verified deal that was not activated
+    fn restore_bytes(client: &Self::AccountId, deal_size: DataCap);
+}
+
+/// Miners that produced the current block, implemented by the runtime
+pub trait BlockProducers {
+    /// Miners rewarded for producing the current block
+    fn block_producers() -> Vec<MinerId>;
+}
+
+impl BlockProducers for () {
+    fn block_producers() -> Vec<MinerId> {
+        Vec::new()
+    }
+}
+
+/// Signed change to a storage power value, used when updating claims
+#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+pub enum PowerDelta<StoragePower> {
+    /// Power is added, e.g. a sector is proven
+    Increase(StoragePower),
+    /// Power is removed, e.g. a sector is faulty or terminated
+    Decrease(StoragePower),
+}
+
+impl<StoragePower: AtLeast32BitUnsigned + Copy> PowerDelta<StoragePower> {
+    /// Applies the delta to `power`, returns `None` on overflow or underflow
+    pub fn apply(&self, power: StoragePower) -> Option<StoragePower> {
+        match self {
+            PowerDelta::Increase(delta) => power.checked_add(delta),
+            PowerDelta::Decrease(delta) => power.checked_sub(delta),
+        }
+    }
 }
 
 /// Struct that stores the claimed storage from a miner, used when submitting PoRep to ensure miner has claims
@@ -29,11 +206,36 @@ pub trait Power {
 #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, Default)]
 pub struct Claim<StoragePower> {
     /// Raw Bytes Stored by the miner
-    raw_bytes_power: StoragePower,
+    pub raw_bytes_power: StoragePower,
     /// Quality Adjusted Power
     /// This is the raw bytes * Sector Quality Multiplier (when committing storage)
-    /// It is equal to raw_bytes_power for now
-    quality_adjusted_power: StoragePower,
+    /// The multiplier weights the deal and verified deal space time of a sector against its
+    /// committed capacity
+    pub quality_adjusted_power: StoragePower,
+}
+
+/// Proof types a sector can be sealed with
+/// following https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/abi/sector.go
+#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+pub enum RegisteredSealProof {
+    StackedDrg2KiBV1,
+    StackedDrg8MiBV1,
+    StackedDrg512MiBV1,
+    StackedDrg32GiBV1,
+    StackedDrg64GiBV1,
+}
+
+impl RegisteredSealProof {
+    /// Size in bytes of a sector sealed with this proof type
+    pub fn sector_size(&self) -> u64 {
+        match self {
+            RegisteredSealProof::StackedDrg2KiBV1 => 2 << 10,
+            RegisteredSealProof::StackedDrg8MiBV1 => 8 << 20,
+            RegisteredSealProof::StackedDrg512MiBV1 => 512 << 20,
+            RegisteredSealProof::StackedDrg32GiBV1 => 32 << 30,
+            RegisteredSealProof::StackedDrg64GiBV1 => 64 << 30,
+        }
+    }
 }
 
 #[derive(Encode, Decode, Default)]
@@ -0,0 +1,144 @@
+use sp_runtime::{
+    traits::{Bounded, CheckedDiv, One, Saturating, Zero},
+    FixedPointNumber, FixedU128,
+};
+
+/// Natural logarithm of 2
+pub fn ln_2() -> FixedU128 {
+    FixedU128::from_inner(693_147_180_559_945_309)
+}
+
+/// Exponential function, saturates at the maximum value of `FixedU128`
+pub fn exp(x: FixedU128) -> FixedU128 {
+    // x = k * ln(2) + r with 0 <= r < ln(2), so e^x = 2^k * e^r where e^r is a fast converging
+    // Taylor series
+    let k = x.into_inner() / ln_2().into_inner();
+    if k >= 128 {
+        return Bounded::max_value();
+    }
+    let r = x.saturating_sub(FixedU128::saturating_from_integer(k).saturating_mul(ln_2()));
+
+    let mut sum = FixedU128::one();
+    let mut term = FixedU128::one();
+    let mut n = 1u128;
+    while !term.is_zero() {
+        term = term
+            .saturating_mul(r)
+            .checked_div(&FixedU128::saturating_from_integer(n))
+            .unwrap_or_else(Zero::zero);
+        sum = sum.saturating_add(term);
+        n += 1;
+    }
+    sum.saturating_mul(FixedU128::saturating_from_integer(1u128 << k))
+}
+
+/// Exponential function of `-x`
+pub fn exp_neg(x: FixedU128) -> FixedU128 {
+    exp(x).reciprocal().unwrap_or_else(Zero::zero)
+}
+
+/// Natural logarithm, `x` must be at least one as `FixedU128` is unsigned, zero is returned
+/// otherwise
+pub fn ln(x: FixedU128) -> FixedU128 {
+    if x <= FixedU128::one() {
+        return Zero::zero();
+    }
+    // x = 2^k * m with 1 <= m < 2, so ln(x) = k * ln(2) + ln(m) where
+    // ln(m) = 2 * atanh((m - 1) / (m + 1)) is a fast converging series
+    let integer = x.into_inner() / FixedU128::accuracy();
+    let k = 127 - integer.leading_zeros();
+    let m = FixedU128::from_inner(x.into_inner() >> k);
+    let z = m
+        .saturating_sub(FixedU128::one())
+        .checked_div(&m.saturating_add(FixedU128::one()))
+        .unwrap_or_else(Zero::zero);
+    let z_squared = z.saturating_mul(z);
+
+    let mut sum = FixedU128::zero();
+    let mut power = z;
+    let mut n = 1u128;
+    while !power.is_zero() {
+        sum = sum.saturating_add(
+            power
+                .checked_div(&FixedU128::saturating_from_integer(n))
+                .unwrap_or_else(Zero::zero),
+        );
+        power = power.saturating_mul(z_squared);
+        n += 2;
+    }
+    FixedU128::saturating_from_integer(k)
+        .saturating_mul(ln_2())
+        .saturating_add(sum.saturating_mul(FixedU128::saturating_from_integer(2u32)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_f64(x: FixedU128) -> f64 {
+        x.into_inner() as f64 / FixedU128::accuracy() as f64
+    }
+
+    fn assert_approx(actual: FixedU128, expected: f64) {
+        let error = (to_f64(actual) - expected).abs() / expected.abs().max(1.0);
+        assert!(error < 1e-12, "{} != {}", to_f64(actual), expected);
+    }
+
+    #[test]
+    fn exp_matches_known_values() {
+        assert_eq!(exp(Zero::zero()), FixedU128::one());
+        assert_approx(exp(FixedU128::one()), std::f64::consts::E);
+        assert_approx(exp(FixedU128::saturating_from_rational(1, 2)), 0.5f64.exp());
+        assert_approx(exp(ln_2()), 2.0);
+        assert_approx(exp(FixedU128::saturating_from_integer(40)), 40f64.exp());
+    }
+
+    #[test]
+    fn exp_saturates_for_large_inputs() {
+        // e^50 is above the maximum value of `FixedU128`, about 3.4e20
+        assert_eq!(
+            exp(FixedU128::saturating_from_integer(50)),
+            Bounded::max_value()
+        );
+        // 100 / ln(2) >= 128 returns early
+        assert_eq!(
+            exp(FixedU128::saturating_from_integer(100)),
+            Bounded::max_value()
+        );
+        assert_eq!(exp(Bounded::max_value()), Bounded::max_value());
+    }
+
+    #[test]
+    fn exp_neg_matches_known_values() {
+        assert_eq!(exp_neg(Zero::zero()), FixedU128::one());
+        assert_approx(exp_neg(FixedU128::one()), (-1f64).exp());
+        assert_approx(
+            exp_neg(FixedU128::saturating_from_integer(10)),
+            (-10f64).exp(),
+        );
+        assert_eq!(
+            exp_neg(FixedU128::saturating_from_integer(100)),
+            Zero::zero()
+        );
+    }
+
+    #[test]
+    fn ln_matches_known_values() {
+        assert_eq!(ln(FixedU128::one()), Zero::zero());
+        assert_eq!(ln(FixedU128::saturating_from_integer(2)), ln_2());
+        assert_approx(ln_2(), std::f64::consts::LN_2);
+        assert_approx(ln(exp(FixedU128::one())), 1.0);
+        assert_approx(
+            ln(FixedU128::saturating_from_integer(10)),
+            std::f64::consts::LN_10,
+        );
+        assert_approx(
+            ln(FixedU128::saturating_from_integer(
+                100_000_000_000_000_000_000u128,
+            )),
+            46.051_701_859_880_914,
+        );
+        // Values below one are not supported by the unsigned type
+        assert_eq!(ln(FixedU128::saturating_from_rational(1, 2)), Zero::zero());
+    }
+}
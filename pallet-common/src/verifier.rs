@@ -0,0 +1,417 @@
+use crate::{DealId, RegisteredSealProof, SectorNumber};
+use codec::{Decode, Encode};
+use frame_support::RuntimeDebug;
+use sp_runtime::traits::{BlakeTwo256, Hash};
+use sp_std::vec::Vec;
+
+/// Verifies the proofs submitted by miners, implemented by the runtime
+/// Real Filecoin proofs are verified off the runtime, this trait allows them to be swapped with
+/// the implementations below for development and tests
+pub trait ProofVerifier {
+    /// Verifies the seal proof (PoRep) of a pre-committed sector
+    fn verify_seal(info: &SealVerifyInfo) -> bool;
+    /// Verifies a WindowPoSt over the sectors of the challenged partitions
+    fn verify_window_post(info: &WindowPoStVerifyInfo) -> bool;
+    /// Verifies a WinningPoSt submitted with an election proof
+    fn verify_winning_post(info: &WinningPoStVerifyInfo) -> bool;
+    /// Verifies the VRF output of a worker over the election randomness
+    fn verify_election_proof(info: &ElectionProofVerifyInfo) -> bool;
+}
+
+/// Verifies the evidence of consensus faults, implemented by the runtime
+/// Block headers are checked off the runtime like proofs, `()` rejects every evidence
+pub trait ConsensusFaultVerifier<BlockNumber> {
+    /// Consensus fault proven by the block headers of `info`, `None` if they are not signed by
+    /// the worker of the miner or do not prove a fault
+    fn verify_consensus_fault(
+        info: &ConsensusFaultVerifyInfo,
+    ) -> Option<ConsensusFault<BlockNumber>>;
+}
+
+impl<BlockNumber> ConsensusFaultVerifier<BlockNumber> for () {
+    fn verify_consensus_fault(_: &ConsensusFaultVerifyInfo) -> Option<ConsensusFault<BlockNumber>> {
+        None
+    }
+}
+
+/// Information needed to verify the seal proof of a sector
+/// following https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/abi/proof.go
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+pub struct SealVerifyInfo {
+    pub seal_proof: RegisteredSealProof,
+    /// SCALE encoded account of the miner that sealed the sector
+    pub prover: Vec<u8>,
+    pub sector_number: SectorNumber,
+    /// CommR of the sealed sector
+    pub sealed_cid: Vec<u8>,
+    pub deal_ids: Vec<DealId>,
+    /// Randomness drawn at the seal randomness epoch of the pre-commit
+    pub randomness: Vec<u8>,
+    /// Randomness drawn after the pre-commit challenge delay
+    pub interactive_randomness: Vec<u8>,
+    pub proof: Vec<u8>,
+}
+
+/// Sector information needed to verify proofs of spacetime
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+pub struct SectorInfo {
+    pub seal_proof: RegisteredSealProof,
+    pub sector_number: SectorNumber,
+    /// CommR of the sealed sector
+    pub sealed_cid: Vec<u8>,
+}
+
+/// Information needed to verify a WindowPoSt
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+pub struct WindowPoStVerifyInfo {
+    /// SCALE encoded account of the miner that submitted the proof
+    pub prover: Vec<u8>,
+    /// Randomness drawn at the challenge epoch of the deadline
+    pub randomness: Vec<u8>,
+    /// Sectors of the proven partitions, excluding faults not declared recovered
+    pub challenged_sectors: Vec<SectorInfo>,
+    pub proof: Vec<u8>,
+}
+
+/// Information needed to verify a WinningPoSt
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+pub struct WinningPoStVerifyInfo {
+    /// SCALE encoded account of the miner that submitted the proof
+    pub prover: Vec<u8>,
+    /// Randomness drawn for the election
+    pub randomness: Vec<u8>,
+    /// Sectors challenged for this election
+    pub challenged_sectors: Vec<SectorInfo>,
+    pub proof: Vec<u8>,
+}
+
+/// Information needed to verify an election proof
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+pub struct ElectionProofVerifyInfo {
+    /// SCALE encoded account of the miner running for election
+    pub prover: Vec<u8>,
+    /// SCALE encoded account of the worker whose key produced the VRF output
+    pub worker: Vec<u8>,
+    /// Randomness drawn for the election
+    pub randomness: Vec<u8>,
+    pub vrf_proof: Vec<u8>,
+}
+
+/// Information needed to verify a consensus fault
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+pub struct ConsensusFaultVerifyInfo {
+    /// SCALE encoded account of the miner accused of the fault
+    pub miner: Vec<u8>,
+    /// SCALE encoded account of the worker that must have signed the block headers
+    pub worker: Vec<u8>,
+    pub header1: Vec<u8>,
+    pub header2: Vec<u8>,
+    /// Header of a block the miner did not build on, only needed to prove parent grinding
+    pub header_extra: Vec<u8>,
+}
+
+/// Consensus faults of block producers
+/// following ConsensusFaultType in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/runtime/runtime.go
+#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+pub enum ConsensusFaultType {
+    /// Two blocks produced at the same height
+    DoubleForkMining,
+    /// A block produced without building on a block of the miner's own at the previous height
+    ParentGrinding,
+    /// Two blocks produced on the same parent at different heights
+    TimeOffsetMining,
+}
+
+/// Consensus fault proven by block headers
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+pub struct ConsensusFault<BlockNumber> {
+    /// Height of the latest of the faulty blocks
+    pub epoch: BlockNumber,
+    pub fault_type: ConsensusFaultType,
+}
+
+/// Accepts every proof
+pub struct AlwaysAccept;
+
+impl ProofVerifier for AlwaysAccept {
+    fn verify_seal(_: &SealVerifyInfo) -> bool {
+        true
+    }
+
+    fn verify_window_post(_: &WindowPoStVerifyInfo) -> bool {
+        true
+    }
+
+    fn verify_winning_post(_: &WinningPoStVerifyInfo) -> bool {
+        true
+    }
+
+    fn verify_election_proof(_: &ElectionProofVerifyInfo) -> bool {
+        true
+    }
+}
+
+/// Rejects every proof
+pub struct AlwaysReject;
+
+impl ProofVerifier for AlwaysReject {
+    fn verify_seal(_: &SealVerifyInfo) -> bool {
+        false
+    }
+
+    fn verify_window_post(_: &WindowPoStVerifyInfo) -> bool {
+        false
+    }
+
+    fn verify_winning_post(_: &WinningPoStVerifyInfo) -> bool {
+        false
+    }
+
+    fn verify_election_proof(_: &ElectionProofVerifyInfo) -> bool {
+        false
+    }
+}
+
+/// Deterministic verifier where a valid proof is the Blake2 hash commitment to everything in the
+/// verify info except the proof itself, valid proofs are produced with the `*_proof` functions
+pub struct FakeVerifier;
+
+impl FakeVerifier {
+    /// Proof accepted by `verify_seal` for `info`, `info.proof` is ignored
+    pub fn seal_proof(info: &SealVerifyInfo) -> Vec<u8> {
+        Self::commitment(
+            b"seal",
+            &SealVerifyInfo {
+                proof: Vec::new(),
+                ..info.clone()
+            },
+        )
+    }
+
+    /// Proof accepted by `verify_window_post` for `info`, `info.proof` is ignored
+    pub fn window_post_proof(info: &WindowPoStVerifyInfo) -> Vec<u8> {
+        Self::commitment(
+            b"window_post",
+            &WindowPoStVerifyInfo {
+                proof: Vec::new(),
+                ..info.clone()
+            },
+        )
+    }
+
+    /// Proof accepted by `verify_winning_post` for `info`, `info.proof` is ignored
+    pub fn winning_post_proof(info: &WinningPoStVerifyInfo) -> Vec<u8> {
+        Self::commitment(
+            b"winning_post",
+            &WinningPoStVerifyInfo {
+                proof: Vec::new(),
+                ..info.clone()
+            },
+        )
+    }
+
+    /// Proof accepted by `verify_election_proof` for `info`, `info.vrf_proof` is ignored
+    pub fn election_proof(info: &ElectionProofVerifyInfo) -> Vec<u8> {
+        Self::commitment(
+            b"election",
+            &ElectionProofVerifyInfo {
+                vrf_proof: Vec::new(),
+                ..info.clone()
+            },
+        )
+    }
+
+    fn commitment<E: Encode>(domain: &[u8], data: &E) -> Vec<u8> {
+        BlakeTwo256::hash_of(&(domain, data)).as_ref().to_vec()
+    }
+}
+
+impl ProofVerifier for FakeVerifier {
+    fn verify_seal(info: &SealVerifyInfo) -> bool {
+        info.proof == Self::seal_proof(info)
+    }
+
+    fn verify_window_post(info: &WindowPoStVerifyInfo) -> bool {
+        info.proof == Self::window_post_proof(info)
+    }
+
+    fn verify_winning_post(info: &WinningPoStVerifyInfo) -> bool {
+        info.proof == Self::winning_post_proof(info)
+    }
+
+    fn verify_election_proof(info: &ElectionProofVerifyInfo) -> bool {
+        info.vrf_proof == Self::election_proof(info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sectors() -> Vec<SectorInfo> {
+        vec![SectorInfo {
+            seal_proof: RegisteredSealProof::StackedDrg2KiBV1,
+            sector_number: 1,
+            sealed_cid: vec![1, 2, 3],
+        }]
+    }
+
+    fn seal_info() -> SealVerifyInfo {
+        SealVerifyInfo {
+            seal_proof: RegisteredSealProof::StackedDrg2KiBV1,
+            prover: vec![1],
+            sector_number: 1,
+            sealed_cid: vec![1, 2, 3],
+            deal_ids: vec![7],
+            randomness: vec![4],
+            interactive_randomness: vec![5],
+            proof: Vec::new(),
+        }
+    }
+
+    fn window_post_info() -> WindowPoStVerifyInfo {
+        WindowPoStVerifyInfo {
+            prover: vec![1],
+            randomness: vec![4],
+            challenged_sectors: sectors(),
+            proof: Vec::new(),
+        }
+    }
+
+    fn winning_post_info() -> WinningPoStVerifyInfo {
+        WinningPoStVerifyInfo {
+            prover: vec![1],
+            randomness: vec![4],
+            challenged_sectors: sectors(),
+            proof: Vec::new(),
+        }
+    }
+
+    fn election_info() -> ElectionProofVerifyInfo {
+        ElectionProofVerifyInfo {
+            prover: vec![1],
+            worker: vec![2],
+            randomness: vec![4],
+            vrf_proof: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn always_accept_and_always_reject_ignore_proofs() {
+        assert!(AlwaysAccept::verify_seal(&seal_info()));
+        assert!(AlwaysAccept::verify_window_post(&window_post_info()));
+        assert!(AlwaysAccept::verify_winning_post(&winning_post_info()));
+        assert!(AlwaysAccept::verify_election_proof(&election_info()));
+
+        let seal = SealVerifyInfo {
+            proof: FakeVerifier::seal_proof(&seal_info()),
+            ..seal_info()
+        };
+        assert!(!AlwaysReject::verify_seal(&seal));
+        assert!(!AlwaysReject::verify_window_post(&window_post_info()));
+        assert!(!AlwaysReject::verify_winning_post(&winning_post_info()));
+        assert!(!AlwaysReject::verify_election_proof(&election_info()));
+    }
+
+    #[test]
+    fn fake_verifier_accepts_seal_proof_for_same_info_only() {
+        let info = SealVerifyInfo {
+            proof: FakeVerifier::seal_proof(&seal_info()),
+            ..seal_info()
+        };
+        assert!(FakeVerifier::verify_seal(&info));
+
+        assert!(!FakeVerifier::verify_seal(&SealVerifyInfo {
+            prover: vec![2],
+            ..info.clone()
+        }));
+        assert!(!FakeVerifier::verify_seal(&SealVerifyInfo {
+            randomness: vec![5],
+            ..info.clone()
+        }));
+        assert!(!FakeVerifier::verify_seal(&SealVerifyInfo {
+            interactive_randomness: vec![6],
+            ..info.clone()
+        }));
+        assert!(!FakeVerifier::verify_seal(&SealVerifyInfo {
+            sealed_cid: vec![3, 2, 1],
+            ..info.clone()
+        }));
+        assert!(!FakeVerifier::verify_seal(&SealVerifyInfo {
+            proof: vec![1, 2, 3, 4],
+            ..info
+        }));
+    }
+
+    #[test]
+    fn fake_verifier_accepts_post_proofs_for_same_info_only() {
+        let window_post = WindowPoStVerifyInfo {
+            proof: FakeVerifier::window_post_proof(&window_post_info()),
+            ..window_post_info()
+        };
+        assert!(FakeVerifier::verify_window_post(&window_post));
+        assert!(!FakeVerifier::verify_window_post(&WindowPoStVerifyInfo {
+            prover: vec![2],
+            ..window_post.clone()
+        }));
+        assert!(!FakeVerifier::verify_window_post(&WindowPoStVerifyInfo {
+            randomness: vec![5],
+            ..window_post.clone()
+        }));
+        assert!(!FakeVerifier::verify_window_post(&WindowPoStVerifyInfo {
+            challenged_sectors: Vec::new(),
+            ..window_post
+        }));
+
+        let winning_post = WinningPoStVerifyInfo {
+            proof: FakeVerifier::winning_post_proof(&winning_post_info()),
+            ..winning_post_info()
+        };
+        assert!(FakeVerifier::verify_winning_post(&winning_post));
+        assert!(!FakeVerifier::verify_winning_post(&WinningPoStVerifyInfo {
+            prover: vec![2],
+            ..winning_post.clone()
+        }));
+        assert!(!FakeVerifier::verify_winning_post(&WinningPoStVerifyInfo {
+            randomness: vec![5],
+            ..winning_post.clone()
+        }));
+        assert!(!FakeVerifier::verify_winning_post(&WinningPoStVerifyInfo {
+            challenged_sectors: Vec::new(),
+            ..winning_post
+        }));
+
+        // Proofs are bound to their kind, a WindowPoSt is not a valid WinningPoSt
+        assert!(!FakeVerifier::verify_winning_post(&WinningPoStVerifyInfo {
+            proof: FakeVerifier::window_post_proof(&window_post_info()),
+            ..winning_post_info()
+        }));
+    }
+
+    #[test]
+    fn fake_verifier_accepts_election_proof_for_same_info_only() {
+        let info = ElectionProofVerifyInfo {
+            vrf_proof: FakeVerifier::election_proof(&election_info()),
+            ..election_info()
+        };
+        assert!(FakeVerifier::verify_election_proof(&info));
+        assert!(!FakeVerifier::verify_election_proof(
+            &ElectionProofVerifyInfo {
+                prover: vec![2],
+                ..info.clone()
+            }
+        ));
+        assert!(!FakeVerifier::verify_election_proof(
+            &ElectionProofVerifyInfo {
+                worker: vec![3],
+                ..info.clone()
+            }
+        ));
+        assert!(!FakeVerifier::verify_election_proof(
+            &ElectionProofVerifyInfo {
+                randomness: vec![5],
+                ..info
+            }
+        ));
+    }
+}
@@ -0,0 +1,205 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(test)]
+mod mock;
+
+// `pallet::Module` is created by `pallet` macro
+pub use pallet::{BlockWinners, Config, Error, Event, Module, Pallet};
+
+#[frame_support::pallet]
+pub mod pallet {
+    use codec::Encode;
+    use frame_support::pallet_prelude::{
+        ensure, DispatchResultWithPostInfo, Get, Hooks, IsType, PhantomData, StorageValue,
+        ValueQuery,
+    };
+    use frame_support::weights::Weight;
+    use frame_system::pallet_prelude::{ensure_signed, BlockNumberFor, OriginFor};
+    use sp_runtime::traits::{
+        BlakeTwo256, CheckedDiv, Hash, One, SaturatedConversion, Saturating, Zero,
+    };
+    use sp_runtime::{FixedPointNumber, FixedU128};
+    use sp_std::vec::Vec;
+
+    use pallet_common::{
+        exp_neg, AccountIdConversion, BlockProducers, ElectionProofVerifyInfo, MinerAccounts,
+        MinerId, Power, ProofVerifier, WinningPoStSectors, WinningPoStVerifyInfo,
+    };
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config {
+        type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+        /// Power table the election is weighted by
+        type Power: Power<AccountId = Self::AccountId, BlockNumber = Self::BlockNumber>;
+        /// Workers and sectors of the miners running for election
+        type Miners: MinerAccounts<AccountId = Self::AccountId>
+            + WinningPoStSectors<AccountId = Self::AccountId>;
+        type ProofVerifier: ProofVerifier;
+        /// Expected number of leaders of a block, the win counts of all miners sum up to it on
+        /// average
+        type ExpectedLeadersPerEpoch: Get<u64>;
+        /// Number of blocks before the current block the power of miners is looked up at, at most
+        /// the number of blocks the power history is retained for
+        type ElectionLookback: Get<Self::BlockNumber>;
+    }
+
+    #[pallet::pallet]
+    #[pallet::generate_store(pub(super) trait Store)]
+    pub struct Pallet<T>(PhantomData<T>);
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        // Benchmark not accurate
+        fn on_initialize(_now: BlockNumberFor<T>) -> Weight {
+            BlockWinners::<T>::kill();
+            T::DbWeight::get().writes(1)
+        }
+    }
+
+    /// Miners elected to produce the current block and their win counts
+    #[pallet::storage]
+    #[pallet::getter(fn block_winners)]
+    pub type BlockWinners<T: Config> = StorageValue<_, Vec<(T::AccountId, u64)>, ValueQuery>;
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    #[pallet::metadata(T::AccountId = "AccountID")]
+    pub enum Event<T: Config> {
+        /// Emits miner address and its win count
+        MinerElected(T::AccountId, u64),
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        NoSuchMiner,
+        InvalidSigner,
+        AlreadyElected,
+        NotEligible,
+        NoPower,
+        InvalidElectionProof,
+        NotElected,
+        InvalidWinningPoSt,
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        // Benchmark not accurate
+        #[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+        pub fn submit_election_proof(
+            origin: OriginFor<T>,
+            miner: T::AccountId,
+            vrf_proof: Vec<u8>,
+            winning_post_proof: Vec<u8>,
+        ) -> DispatchResultWithPostInfo {
+            // following the block validation of https://spec.filecoin.io/algorithms/expected_consensus/
+            // Note: The election and WinningPoSt randomness is the hash of the parent block
+            let signer = ensure_signed(origin)?;
+            let worker = T::Miners::worker(&miner).ok_or(Error::<T>::NoSuchMiner)?;
+            ensure!(signer == worker, Error::<T>::InvalidSigner);
+            let mut winners = BlockWinners::<T>::get();
+            ensure!(
+                !winners.iter().any(|(winner, _)| winner == &miner),
+                Error::<T>::AlreadyElected
+            );
+            // Eligibility is checked against the same power the election is weighted by
+            let current_block = <frame_system::Module<T>>::block_number();
+            let lookback = current_block.saturating_sub(T::ElectionLookback::get());
+            ensure!(
+                T::Power::is_eligible_at(&miner, lookback),
+                Error::<T>::NotEligible
+            );
+            let power = T::Power::claim_at(&miner, lookback)
+                .ok_or(Error::<T>::NoPower)?
+                .quality_adjusted_power;
+            let total_power = T::Power::total_power_at(lookback)
+                .ok_or(Error::<T>::NoPower)?
+                .quality_adjusted_power;
+
+            let randomness =
+                <frame_system::Module<T>>::block_hash(current_block.saturating_sub(One::one()))
+                    .as_ref()
+                    .to_vec();
+            let election_info = ElectionProofVerifyInfo {
+                prover: miner.encode(),
+                worker: worker.encode(),
+                randomness: randomness.clone(),
+                vrf_proof,
+            };
+            ensure!(
+                T::ProofVerifier::verify_election_proof(&election_info),
+                Error::<T>::InvalidElectionProof
+            );
+            let win_count = Self::win_count(
+                &election_info.vrf_proof,
+                power.saturated_into::<u128>(),
+                total_power.saturated_into::<u128>(),
+            );
+            ensure!(win_count > 0, Error::<T>::NotElected);
+
+            let winning_post_info = WinningPoStVerifyInfo {
+                prover: miner.encode(),
+                challenged_sectors: T::Miners::challenged_sectors(&miner, &randomness),
+                randomness,
+                proof: winning_post_proof,
+            };
+            ensure!(
+                T::ProofVerifier::verify_winning_post(&winning_post_info),
+                Error::<T>::InvalidWinningPoSt
+            );
+
+            winners.push((miner.clone(), win_count));
+            BlockWinners::<T>::put(winners);
+            Self::deposit_event(Event::MinerElected(miner, win_count));
+
+            Ok(().into())
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        /// Number of times a miner with `power` out of `total_power` wins the election with
+        /// `vrf_proof`, a Poisson draw with the expected number of leaders times the power share
+        /// of the miner as mean
+        pub fn win_count(vrf_proof: &[u8], power: u128, total_power: u128) -> u64 {
+            // following ComputeWinCount in https://github.com/filecoin-project/lotus/blob/master/chain/types/electionproof.go
+            // Note: The ticket is the first 8 bytes of the hash of the VRF proof, uniform in
+            // [0, 1), and the win count is capped at 3 times the expected number of leaders
+            if power.is_zero() || total_power.is_zero() {
+                return 0;
+            }
+            let expected_leaders = T::ExpectedLeadersPerEpoch::get();
+            let lambda = FixedU128::saturating_from_rational(power, total_power)
+                .saturating_mul(FixedU128::saturating_from_integer(expected_leaders));
+            let ticket = FixedU128::saturating_from_rational(
+                BlakeTwo256::hash(vrf_proof).to_low_u64_le(),
+                1u128 << 64,
+            );
+
+            // Probability of winning exactly and more than `win_count` times
+            let mut probability = exp_neg(lambda);
+            let mut tail = FixedU128::one().saturating_sub(probability);
+            let max_win_count = expected_leaders.saturating_mul(3);
+            let mut win_count = 0;
+            while ticket < tail && win_count < max_win_count {
+                win_count += 1;
+                probability = probability
+                    .saturating_mul(lambda)
+                    .checked_div(&FixedU128::saturating_from_integer(win_count))
+                    .unwrap_or_else(Zero::zero);
+                tail = tail.saturating_sub(probability);
+            }
+            win_count
+        }
+    }
+
+    impl<T: Config> BlockProducers for Pallet<T> {
+        fn block_producers() -> Vec<MinerId> {
+            BlockWinners::<T>::get()
+                .iter()
+                .filter_map(|(miner, _)| MinerId::try_from_account(miner))
+                .collect()
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use crate as pallet_election;
+use frame_support::parameter_types;
+use frame_system as system;
+use pallet_common::{
+    FakeVerifier, MinerAccounts, RegisteredSealProof, SectorInfo, WinningPoStSectors,
+};
+use sp_core::H256;
+use sp_runtime::{
+    testing::Header,
+    traits::{BlakeTwo256, Convert, IdentityLookup},
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+    pub enum Test where
+        Block = Block,
+        NodeBlock = Block,
+        UncheckedExtrinsic = UncheckedExtrinsic,
+        {
+            System: frame_system::{Module, Call, Config, Storage, Event<T>},
+            Power: pallet_power::{Module, Storage, Event<T>},
+            Election: pallet_election::{Module, Call, Storage, Event<T>},
+        }
+);
+
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+    pub const SS58Prefix: u8 = 42;
+}
+
+// not sure where the Call, Event, PalletInfo comes from
+impl system::Config for Test {
+    type BaseCallFilter = ();
+    type BlockWeights = ();
+    type BlockLength = ();
+    type DbWeight = ();
+    type Origin = Origin;
+    type Call = Call;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = u64;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = Event;
+    type BlockHashCount = BlockHashCount;
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = ();
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = SS58Prefix;
+}
+
+pub const SEAL_PROOF: RegisteredSealProof = RegisteredSealProof::StackedDrg2KiBV1;
+
+/// Consensus minimum power of 10 sectors
+pub struct ConsensusMinerMinPower;
+
+impl Convert<RegisteredSealProof, u128> for ConsensusMinerMinPower {
+    fn convert(seal_proof: RegisteredSealProof) -> u128 {
+        seal_proof.sector_size() as u128 * 10
+    }
+}
+
+parameter_types! {
+    pub ConsensusMinerMinMiners: u64 = 3;
+    pub const PowerHistoryRetention: u64 = 30;
+}
+
+impl pallet_power::Config for Test {
+    type Event = Event;
+    type PeerId = Vec<u8>;
+    type StoragePower = u128;
+    type ConsensusMinerMinPower = ConsensusMinerMinPower;
+    type ConsensusMinerMinMiners = ConsensusMinerMinMiners;
+    type PowerHistoryRetention = PowerHistoryRetention;
+}
+
+/// Offset of the worker account from the miner account
+pub const WORKER_OFFSET: u64 = 100;
+
+/// Miners below `WORKER_OFFSET` with `miner + WORKER_OFFSET` as worker, their only challenged
+/// sector is numbered after the miner
+pub struct MockMiners;
+
+impl MinerAccounts for MockMiners {
+    type AccountId = u64;
+
+    fn owner(miner: &u64) -> Option<u64> {
+        Self::worker(miner)
+    }
+
+    fn worker(miner: &u64) -> Option<u64> {
+        if *miner < WORKER_OFFSET {
+            Some(miner + WORKER_OFFSET)
+        } else {
+            None
+        }
+    }
+
+    fn is_controlling_address(miner: &u64, address: &u64) -> bool {
+        Self::worker(miner) == Some(*address)
+    }
+}
+
+impl WinningPoStSectors for MockMiners {
+    type AccountId = u64;
+
+    fn challenged_sectors(miner: &u64, _: &[u8]) -> Vec<SectorInfo> {
+        vec![SectorInfo {
+            seal_proof: SEAL_PROOF,
+            sector_number: *miner,
+            sealed_cid: vec![1, 2, 3],
+        }]
+    }
+}
+
+parameter_types! {
+    pub const ExpectedLeadersPerEpoch: u64 = 5;
+    pub const ElectionLookback: u64 = 10;
+}
+
+impl pallet_election::Config for Test {
+    type Event = Event;
+    type Power = Power;
+    type Miners = MockMiners;
+    type ProofVerifier = FakeVerifier;
+    type ExpectedLeadersPerEpoch = ExpectedLeadersPerEpoch;
+    type ElectionLookback = ElectionLookback;
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    system::GenesisConfig::default()
+        .build_storage::<Test>()
+        .unwrap()
+        .into()
+}
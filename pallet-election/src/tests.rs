@@ -0,0 +1,267 @@
+// Imports created by construct_runtime macros are unresolved by rust analyzer
+use crate as pallet_election;
+use crate::mock::{
+    new_test_ext, Election, Event, MockMiners, Origin, Power, System, Test, SEAL_PROOF,
+    WORKER_OFFSET,
+};
+use crate::Error;
+use codec::Encode;
+use frame_support::{assert_noop, assert_ok, traits::OnInitialize};
+use pallet_common::{
+    ElectionProofVerifyInfo, FakeVerifier, Power as PowerTrait, PowerDelta, WinningPoStSectors,
+    WinningPoStVerifyInfo,
+};
+use sp_core::H256;
+
+// Utility functions
+//
+// Registers miners numbered from 1 with `powers` at block 0 and runs to block 20, elections then
+// look power up at block 10
+fn setup_miners(powers: &[u128]) {
+    for (index, power) in powers.iter().enumerate() {
+        let miner = index as u64 + 1;
+        Power::register_new_miner(&miner, SEAL_PROOF).expect("Registration failed");
+        Power::update_claim(
+            miner,
+            PowerDelta::Increase(*power),
+            PowerDelta::Increase(*power),
+        )
+        .expect("Update failed");
+    }
+    run_to_block(20);
+}
+
+fn run_to_block(n: u64) {
+    while System::block_number() < n {
+        let block = System::block_number() + 1;
+        System::set_block_number(block);
+        Election::on_initialize(block);
+    }
+}
+
+fn randomness() -> Vec<u8> {
+    System::block_hash(System::block_number() - 1)
+        .as_ref()
+        .to_vec()
+}
+
+// VRF proof of the worker of `miner` accepted by `FakeVerifier` in the current block
+fn vrf_proof(miner: u64) -> Vec<u8> {
+    FakeVerifier::election_proof(&ElectionProofVerifyInfo {
+        prover: miner.encode(),
+        worker: (miner + WORKER_OFFSET).encode(),
+        randomness: randomness(),
+        vrf_proof: Vec::new(),
+    })
+}
+
+// WinningPoSt of `miner` accepted by `FakeVerifier` in the current block
+fn winning_post_proof(miner: u64) -> Vec<u8> {
+    FakeVerifier::winning_post_proof(&WinningPoStVerifyInfo {
+        prover: miner.encode(),
+        randomness: randomness(),
+        challenged_sectors: MockMiners::challenged_sectors(&miner, &randomness()),
+        proof: Vec::new(),
+    })
+}
+
+// Sets the hash of the parent block so that `miner` with `power` out of `total_power` wins the
+// election of the current block or not
+fn set_randomness(miner: u64, power: u128, total_power: u128, wins: bool) {
+    let parent = System::block_number() - 1;
+    for byte in 0..=u8::MAX {
+        <frame_system::BlockHash<Test>>::insert(parent, H256::repeat_byte(byte));
+        if (Election::win_count(&vrf_proof(miner), power, total_power) > 0) == wins {
+            return;
+        }
+    }
+    panic!("no randomness found");
+}
+
+#[test]
+fn submit_election_proof_elects_winning_miner() {
+    new_test_ext().execute_with(|| {
+        setup_miners(&[9_000, 1_000]);
+        set_randomness(1, 9_000, 10_000, true);
+        let win_count = Election::win_count(&vrf_proof(1), 9_000, 10_000);
+
+        assert_ok!(Election::submit_election_proof(
+            Origin::signed(1 + WORKER_OFFSET),
+            1,
+            vrf_proof(1),
+            winning_post_proof(1)
+        ));
+        assert_eq!(Election::block_winners(), vec![(1, win_count)]);
+        assert_eq!(
+            System::events().pop().map(|e| e.event),
+            Some(Event::pallet_election(
+                pallet_election::Event::MinerElected(1, win_count)
+            ))
+        );
+
+        assert_noop!(
+            Election::submit_election_proof(
+                Origin::signed(1 + WORKER_OFFSET),
+                1,
+                vrf_proof(1),
+                winning_post_proof(1)
+            ),
+            Error::<Test>::AlreadyElected
+        );
+
+        // Winners are cleared in the next block
+        run_to_block(21);
+        assert!(Election::block_winners().is_empty());
+    });
+}
+
+#[test]
+fn submit_election_proof_rejects_invalid_proofs() {
+    new_test_ext().execute_with(|| {
+        setup_miners(&[9_000, 1_000]);
+        set_randomness(1, 9_000, 10_000, true);
+
+        assert_noop!(
+            Election::submit_election_proof(
+                Origin::signed(1 + WORKER_OFFSET),
+                WORKER_OFFSET,
+                vrf_proof(1),
+                winning_post_proof(1)
+            ),
+            Error::<Test>::NoSuchMiner
+        );
+        assert_noop!(
+            Election::submit_election_proof(
+                Origin::signed(2 + WORKER_OFFSET),
+                1,
+                vrf_proof(1),
+                winning_post_proof(1)
+            ),
+            Error::<Test>::InvalidSigner
+        );
+        assert_noop!(
+            Election::submit_election_proof(
+                Origin::signed(1 + WORKER_OFFSET),
+                1,
+                vrf_proof(2),
+                winning_post_proof(1)
+            ),
+            Error::<Test>::InvalidElectionProof
+        );
+        assert_noop!(
+            Election::submit_election_proof(
+                Origin::signed(1 + WORKER_OFFSET),
+                1,
+                vrf_proof(1),
+                winning_post_proof(2)
+            ),
+            Error::<Test>::InvalidWinningPoSt
+        );
+    });
+}
+
+#[test]
+fn submit_election_proof_rejects_losing_and_ineligible_miners() {
+    new_test_ext().execute_with(|| {
+        setup_miners(&[9_000, 1_000, 0]);
+        set_randomness(2, 1_000, 10_000, false);
+
+        assert_noop!(
+            Election::submit_election_proof(
+                Origin::signed(2 + WORKER_OFFSET),
+                2,
+                vrf_proof(2),
+                winning_post_proof(2)
+            ),
+            Error::<Test>::NotElected
+        );
+        // Miners without power are not eligible
+        assert_noop!(
+            Election::submit_election_proof(
+                Origin::signed(3 + WORKER_OFFSET),
+                3,
+                vrf_proof(3),
+                winning_post_proof(3)
+            ),
+            Error::<Test>::NotEligible
+        );
+
+        // Miners without power at the lookback block are not eligible
+        Power::register_new_miner(&4, SEAL_PROOF).expect("Registration failed");
+        Power::update_claim(4, PowerDelta::Increase(1_000), PowerDelta::Increase(1_000))
+            .expect("Update failed");
+        assert!(Power::is_eligible(&4));
+        assert_noop!(
+            Election::submit_election_proof(
+                Origin::signed(4 + WORKER_OFFSET),
+                4,
+                vrf_proof(4),
+                winning_post_proof(4)
+            ),
+            Error::<Test>::NotEligible
+        );
+    });
+}
+
+#[test]
+fn submit_election_proof_checks_eligibility_at_lookback_block() {
+    new_test_ext().execute_with(|| {
+        setup_miners(&[9_000, 1_000]);
+        set_randomness(1, 9_000, 10_000, true);
+
+        // Miners losing their power after the lookback block are still eligible
+        Power::update_claim(1, PowerDelta::Decrease(9_000), PowerDelta::Decrease(9_000))
+            .expect("Update failed");
+        assert!(!Power::is_eligible(&1));
+        assert_ok!(Election::submit_election_proof(
+            Origin::signed(1 + WORKER_OFFSET),
+            1,
+            vrf_proof(1),
+            winning_post_proof(1)
+        ));
+
+        // Suspended miners are not eligible
+        run_to_block(21);
+        set_randomness(2, 1_000, 10_000, true);
+        Power::suspend_eligibility(&2, 30);
+        assert_noop!(
+            Election::submit_election_proof(
+                Origin::signed(2 + WORKER_OFFSET),
+                2,
+                vrf_proof(2),
+                winning_post_proof(2)
+            ),
+            Error::<Test>::NotEligible
+        );
+    });
+}
+
+#[test]
+fn win_frequency_converges_to_power_share() {
+    new_test_ext().execute_with(|| {
+        let powers: [u128; 3] = [1_000, 3_000, 6_000];
+        let total_power: u128 = powers.iter().sum();
+        let epochs = 10_000u64;
+
+        let mut wins = [0u64; 3];
+        for epoch in 0..epochs {
+            for (miner, power) in powers.iter().enumerate() {
+                let vrf_proof = (epoch, miner as u64).encode();
+                wins[miner] += Election::win_count(&vrf_proof, *power, total_power);
+            }
+        }
+
+        // The win counts of all miners sum up to the 5 expected leaders per block on average
+        let total_wins: u64 = wins.iter().sum();
+        let leaders_per_epoch = total_wins as f64 / epochs as f64;
+        assert!((leaders_per_epoch - 5.0).abs() < 0.1);
+        // and each miner wins in proportion to its power
+        for (miner, power) in powers.iter().enumerate() {
+            let win_share = wins[miner] as f64 / total_wins as f64;
+            let power_share = *power as f64 / total_power as f64;
+            assert!((win_share - power_share).abs() < 0.01);
+        }
+
+        assert_eq!(Election::win_count(&[1, 2, 3], 0, total_power), 0);
+    });
+}
@@ -0,0 +1,657 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(test)]
+mod mock;
+
+use codec::{Decode, Encode};
+use sp_runtime::traits::{AtLeast32BitUnsigned, SaturatedConversion, Saturating};
+pub use sp_std::vec::Vec;
+
+// `pallet::Module` is created by `pallet` macro
+pub use pallet::{
+    Config, DealOpsByEpoch, DealOpsCount, DealOpsCursor, Error, EscrowTable, Event, LockedTable,
+    Module, NextDealId, Pallet, PendingProposals, Proposals, States,
+};
+
+#[frame_support::pallet]
+pub mod pallet {
+    use super::{ClientDealProposal, DealProposal, DealState, Vec};
+    use codec::Encode;
+    use frame_support::pallet_prelude::{
+        ensure, Blake2_128Concat, DispatchResultWithPostInfo, Get, Hooks, IsType, PhantomData,
+        StorageDoubleMap, StorageMap, StorageValue, Twox64Concat, ValueQuery,
+    };
+    use frame_support::traits::{Currency, ExistenceRequirement, OnUnbalanced};
+    use frame_support::weights::Weight;
+    use frame_support::Parameter;
+    use frame_system::pallet_prelude::{ensure_signed, BlockNumberFor, OriginFor};
+    use sp_runtime::traits::{
+        AccountIdConversion, Hash, IdentifyAccount, One, SaturatedConversion, Saturating, Verify,
+        Zero,
+    };
+    use sp_runtime::{DispatchError, ModuleId};
+    use sp_std::collections::{btree_map::BTreeMap, btree_set::BTreeSet};
+
+    use pallet_common::{DataCap, DealId, DealWeight, Market, MinerAccounts, VerifiedRegistry};
+
+    pub type BalanceOf<T> =
+        <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+    pub type DealProposalOf<T> =
+        DealProposal<<T as frame_system::Config>::AccountId, BalanceOf<T>, BlockNumberFor<T>>;
+    pub type ClientDealProposalOf<T> = ClientDealProposal<
+        <T as frame_system::Config>::AccountId,
+        BalanceOf<T>,
+        BlockNumberFor<T>,
+        <T as Config>::OffchainSignature,
+    >;
+    pub type NegativeImbalanceOf<T> = <<T as Config>::Currency as Currency<
+        <T as frame_system::Config>::AccountId,
+    >>::NegativeImbalance;
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config {
+        type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+        /// Currency the escrow of clients and providers is held in
+        type Currency: Currency<Self::AccountId>;
+        /// Miners acting as storage providers
+        type Miners: MinerAccounts<AccountId = Self::AccountId>;
+        /// Signature of a client on its deal proposals
+        type OffchainSignature: Verify<Signer = Self::OffchainPublic> + Parameter;
+        /// Public key of a client, identifies its account
+        type OffchainPublic: IdentifyAccount<AccountId = Self::AccountId>;
+        /// Identifier of the account holding the escrow
+        type ModuleId: Get<ModuleId>;
+        /// Maximum number of blocks between the start and the end of a deal
+        type MaxDealDuration: Get<BlockNumberFor<Self>>;
+        /// Maximum number of scheduled deals and blocks without deals the cron of a block goes
+        /// through, the rest is deferred to the next blocks
+        type MaxDealsPerBlock: Get<u32>;
+        /// Number of blocks between two settlements of the payment of an active deal
+        type DealUpdatesInterval: Get<BlockNumberFor<Self>>;
+        /// Handler for the collateral slashed from providers, burnt when set to `()`
+        type Slashed: OnUnbalanced<NegativeImbalanceOf<Self>>;
+        /// Registry of the DataCap consumed by verified deals
+        type VerifiedRegistry: VerifiedRegistry<AccountId = Self::AccountId>;
+    }
+
+    #[pallet::pallet]
+    #[pallet::generate_store(pub(super) trait Store)]
+    pub struct Pallet<T>(PhantomData<T>);
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_initialize(now: BlockNumberFor<T>) -> Weight {
+            // following CronTick in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/market/market_actor.go
+            // Note: The deals scheduled up to `now` are processed in order from the cursor, each
+            // deal and each block without deals left counts towards `MaxDealsPerBlock`, the rest
+            // is deferred to the next blocks
+            let (mut epoch, mut index) = DealOpsCursor::<T>::get();
+            let mut reads: u64 = 1;
+            let mut writes: u64 = 1;
+            for _ in 0..T::MaxDealsPerBlock::get() {
+                if epoch > now {
+                    break;
+                }
+                let count = DealOpsCount::<T>::get(epoch);
+                reads += 1;
+                if index < count {
+                    // Can not be `None` as the ops of the epoch are only taken here
+                    if let Some(deal_id) = DealOpsByEpoch::<T>::take(epoch, index) {
+                        if let Some(next_update) = Self::process_deal(deal_id, now) {
+                            Self::schedule_deal(next_update, deal_id);
+                        }
+                    }
+                    index += 1;
+                    reads += 6;
+                    writes += 9;
+                }
+                if index >= count {
+                    DealOpsCount::<T>::remove(epoch);
+                    epoch += One::one();
+                    index = 0;
+                    writes += 1;
+                }
+            }
+            DealOpsCursor::<T>::put((epoch, index));
+            T::DbWeight::get().reads_writes(reads, writes)
+        }
+    }
+
+    /// Proposals of published deals
+    #[pallet::storage]
+    #[pallet::getter(fn proposals)]
+    pub type Proposals<T: Config> = StorageMap<_, Twox64Concat, DealId, DealProposalOf<T>>;
+
+    /// State of published deals
+    #[pallet::storage]
+    #[pallet::getter(fn states)]
+    pub type States<T: Config> = StorageMap<_, Twox64Concat, DealId, DealState<BlockNumberFor<T>>>;
+
+    /// Id assigned to the next published deal
+    #[pallet::storage]
+    #[pallet::getter(fn next_deal_id)]
+    pub type NextDealId<T: Config> = StorageValue<_, DealId, ValueQuery>;
+
+    /// Deals scheduled to be processed by the cron at a block, by their index at the block
+    /// following DealOpsByEpoch in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/market/market_state.go
+    #[pallet::storage]
+    #[pallet::getter(fn deal_ops_by_epoch)]
+    pub type DealOpsByEpoch<T: Config> =
+        StorageDoubleMap<_, Twox64Concat, BlockNumberFor<T>, Twox64Concat, u32, DealId>;
+
+    /// Number of deals scheduled at a block, the index of the next deal scheduled at it
+    #[pallet::storage]
+    #[pallet::getter(fn deal_ops_count)]
+    pub type DealOpsCount<T: Config> =
+        StorageMap<_, Twox64Concat, BlockNumberFor<T>, u32, ValueQuery>;
+
+    /// Block and index of the next scheduled deal processed by the cron
+    #[pallet::storage]
+    #[pallet::getter(fn deal_ops_cursor)]
+    pub type DealOpsCursor<T: Config> = StorageValue<_, (BlockNumberFor<T>, u32), ValueQuery>;
+
+    /// Hashes of published proposals, a signed proposal can only be published once
+    #[pallet::storage]
+    #[pallet::getter(fn pending_proposals)]
+    pub type PendingProposals<T: Config> = StorageMap<_, Blake2_128Concat, T::Hash, ()>;
+
+    /// Funds of clients and providers held in escrow by the market
+    #[pallet::storage]
+    #[pallet::getter(fn escrow_table)]
+    pub type EscrowTable<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, BalanceOf<T>, ValueQuery>;
+
+    /// Part of the escrow locked by published deals, as payment or collateral
+    #[pallet::storage]
+    #[pallet::getter(fn locked_table)]
+    pub type LockedTable<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, BalanceOf<T>, ValueQuery>;
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    #[pallet::metadata(T::AccountId = "AccountID", BalanceOf<T> = "Balance")]
+    pub enum Event<T: Config> {
+        /// Emits address and the amount added to its escrow
+        BalanceAdded(T::AccountId, BalanceOf<T>),
+        /// Emits address, recipient and the amount withdrawn from the escrow of the address
+        BalanceWithdrawn(T::AccountId, T::AccountId, BalanceOf<T>),
+        /// Emits provider address and the ids of its published deals
+        DealsPublished(T::AccountId, Vec<DealId>),
+        /// Emits id of the deal that ended and was paid in full
+        DealCompleted(DealId),
+        /// Emits id of the deal that was not activated before its start
+        DealTimedOut(DealId),
+        /// Emits id of the deal whose sector was terminated and the provider collateral slashed
+        DealSlashed(DealId, BalanceOf<T>),
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        InvalidSigner,
+        NoDeals,
+        InvalidProvider,
+        InvalidSignature,
+        InvalidPieceSize,
+        DealStartElapsed,
+        InvalidDealDuration,
+        DuplicateDeal,
+        InsufficientFunds,
+        NoSuchDeal,
+        DealAlreadyActivated,
+        DealEndsAfterSector,
+        DealsExceedSectorSize,
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        // Benchmark not accurate
+        #[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+        pub fn add_balance(
+            origin: OriginFor<T>,
+            address: T::AccountId,
+            amount: BalanceOf<T>,
+        ) -> DispatchResultWithPostInfo {
+            // following AddBalance in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/market/market_actor.go
+            // Note: Any account can add funds to the escrow of a client or provider
+            let signer = ensure_signed(origin)?;
+            T::Currency::transfer(
+                &signer,
+                &Self::account_id(),
+                amount,
+                ExistenceRequirement::AllowDeath,
+            )?;
+            EscrowTable::<T>::mutate(&address, |escrow| *escrow = escrow.saturating_add(amount));
+            Self::deposit_event(Event::BalanceAdded(address, amount));
+
+            Ok(().into())
+        }
+
+        // Benchmark not accurate
+        #[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+        pub fn withdraw_balance(
+            origin: OriginFor<T>,
+            address: T::AccountId,
+            amount: BalanceOf<T>,
+        ) -> DispatchResultWithPostInfo {
+            // following WithdrawBalance in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/market/market_actor.go
+            // Note: The amount withdrawn is capped at the escrow that is not locked, the escrow
+            // of a provider is withdrawn to its owner
+            let signer = ensure_signed(origin)?;
+            let recipient = match T::Miners::owner(&address) {
+                Some(owner) => {
+                    ensure!(
+                        T::Miners::is_controlling_address(&address, &signer),
+                        Error::<T>::InvalidSigner
+                    );
+                    owner
+                }
+                None => {
+                    ensure!(signer == address, Error::<T>::InvalidSigner);
+                    address.clone()
+                }
+            };
+
+            let amount = amount.min(Self::available_balance(&address));
+            T::Currency::transfer(
+                &Self::account_id(),
+                &recipient,
+                amount,
+                ExistenceRequirement::AllowDeath,
+            )?;
+            EscrowTable::<T>::mutate(&address, |escrow| *escrow = escrow.saturating_sub(amount));
+            Self::deposit_event(Event::BalanceWithdrawn(address, recipient, amount));
+
+            Ok(().into())
+        }
+
+        // Benchmark not accurate
+        #[pallet::weight(10_000 + T::DbWeight::get().writes(1 + 5 * deals.len() as u64))]
+        pub fn publish_storage_deals(
+            origin: OriginFor<T>,
+            deals: Vec<ClientDealProposalOf<T>>,
+        ) -> DispatchResultWithPostInfo {
+            // following PublishStorageDeals in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/market/market_actor.go
+            // Note: Every deal is validated and the funds of all deals are checked before any
+            // of them is published
+            let signer = ensure_signed(origin)?;
+            let provider = deals
+                .first()
+                .map(|deal| deal.proposal.provider.clone())
+                .ok_or(Error::<T>::NoDeals)?;
+            ensure!(
+                T::Miners::is_controlling_address(&provider, &signer),
+                Error::<T>::InvalidSigner
+            );
+
+            let current_block = <frame_system::Module<T>>::block_number();
+            let mut hashes = Vec::new();
+            let mut locks: BTreeMap<T::AccountId, BalanceOf<T>> = BTreeMap::new();
+            let mut verified_deal_sizes: BTreeMap<T::AccountId, Vec<DataCap>> = BTreeMap::new();
+            for deal in deals.iter() {
+                let proposal = &deal.proposal;
+                ensure!(proposal.provider == provider, Error::<T>::InvalidProvider);
+                ensure!(
+                    deal.client_signature
+                        .verify(&proposal.encode()[..], &proposal.client),
+                    Error::<T>::InvalidSignature
+                );
+                ensure!(
+                    proposal.piece_size.is_power_of_two(),
+                    Error::<T>::InvalidPieceSize
+                );
+                ensure!(
+                    proposal.start_epoch > current_block,
+                    Error::<T>::DealStartElapsed
+                );
+                ensure!(
+                    proposal.end_epoch > proposal.start_epoch
+                        && proposal.duration() <= T::MaxDealDuration::get(),
+                    Error::<T>::InvalidDealDuration
+                );
+                let hash = T::Hashing::hash_of(proposal);
+                ensure!(
+                    !PendingProposals::<T>::contains_key(&hash) && !hashes.contains(&hash),
+                    Error::<T>::DuplicateDeal
+                );
+                hashes.push(hash);
+
+                let client_lock = locks
+                    .entry(proposal.client.clone())
+                    .or_insert_with(Zero::zero);
+                *client_lock = client_lock.saturating_add(proposal.client_balance_requirement());
+                let provider_lock = locks.entry(provider.clone()).or_insert_with(Zero::zero);
+                *provider_lock = provider_lock.saturating_add(proposal.provider_collateral);
+                if proposal.verified_deal {
+                    verified_deal_sizes
+                        .entry(proposal.client.clone())
+                        .or_insert_with(Vec::new)
+                        .push(proposal.piece_size);
+                }
+            }
+            for (address, lock) in locks.iter() {
+                ensure!(
+                    Self::available_balance(address) >= *lock,
+                    Error::<T>::InsufficientFunds
+                );
+            }
+            for (client, deal_sizes) in verified_deal_sizes.iter() {
+                T::VerifiedRegistry::can_use_bytes(client, deal_sizes)?;
+            }
+
+            for (address, lock) in locks {
+                LockedTable::<T>::mutate(&address, |locked| *locked = locked.saturating_add(lock));
+            }
+            for (client, deal_sizes) in verified_deal_sizes {
+                T::VerifiedRegistry::use_bytes(&client, &deal_sizes);
+            }
+            let mut deal_ids = Vec::new();
+            for (deal, hash) in deals.into_iter().zip(hashes) {
+                let deal_id = NextDealId::<T>::get();
+                NextDealId::<T>::put(deal_id + 1);
+                // The deal is first processed once it started, to time it out if it was not
+                // activated
+                Self::schedule_deal(deal.proposal.start_epoch + One::one(), deal_id);
+                Proposals::<T>::insert(deal_id, deal.proposal);
+                States::<T>::insert(deal_id, DealState::default());
+                PendingProposals::<T>::insert(hash, ());
+                deal_ids.push(deal_id);
+            }
+            Self::deposit_event(Event::DealsPublished(provider, deal_ids));
+
+            Ok(().into())
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        /// Account holding the escrow of clients and providers
+        pub fn account_id() -> T::AccountId {
+            T::ModuleId::get().into_account()
+        }
+
+        /// Escrow of the address that is not locked by deals
+        pub fn available_balance(address: &T::AccountId) -> BalanceOf<T> {
+            EscrowTable::<T>::get(address).saturating_sub(LockedTable::<T>::get(address))
+        }
+
+        /// Settles the payment of an active deal up to `now`, removes the deal once it ended,
+        /// was slashed or was not activated before its start.
+        /// Returns the block the deal is next processed at, `None` once it is removed
+        fn process_deal(deal_id: DealId, now: BlockNumberFor<T>) -> Option<BlockNumberFor<T>> {
+            // following updatePendingDealState in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/market/market_state.go
+            let (proposal, mut state) =
+                match (Proposals::<T>::get(deal_id), States::<T>::get(deal_id)) {
+                    (Some(proposal), Some(state)) => (proposal, state),
+                    _ => return None,
+                };
+            // Nothing is due before the start of a deal, deals slashed before their start are
+            // kept until then so the hash of their proposal prevents it from being published
+            // again
+            if now <= proposal.start_epoch {
+                return Some(proposal.start_epoch + One::one());
+            }
+            if state.sector_start_epoch.is_none() {
+                // following processDealInitTimedOut, the provider forfeits its collateral
+                Self::unlock_balance(&proposal.client, proposal.client_balance_requirement());
+                Self::slash_provider_collateral(&proposal);
+                if proposal.verified_deal {
+                    T::VerifiedRegistry::restore_bytes(&proposal.client, proposal.piece_size);
+                }
+                Self::remove_deal(deal_id, &proposal);
+                Self::deposit_event(Event::DealTimedOut(deal_id));
+                return None;
+            }
+
+            // The payment accrues from the start of the deal until it ends or is slashed
+            let payment_start = state
+                .last_updated_epoch
+                .map_or(proposal.start_epoch, |updated| {
+                    updated.max(proposal.start_epoch)
+                });
+            let payment_end = state
+                .slash_epoch
+                .map_or(now, |slashed| slashed.min(now))
+                .min(proposal.end_epoch);
+            if payment_end > payment_start {
+                let payment = proposal
+                    .storage_price_per_epoch
+                    .saturating_mul((payment_end - payment_start).saturated_into());
+                Self::transfer_balance(&proposal.client, &proposal.provider, payment);
+            }
+
+            if state.slash_epoch.is_some() {
+                // following processDealSlashed, the client is refunded the payment of the rest
+                // of the deal
+                let remaining_fee = proposal.storage_price_per_epoch.saturating_mul(
+                    proposal
+                        .end_epoch
+                        .saturating_sub(payment_end.max(proposal.start_epoch))
+                        .saturated_into(),
+                );
+                Self::unlock_balance(
+                    &proposal.client,
+                    remaining_fee.saturating_add(proposal.client_collateral),
+                );
+                let slashed = Self::slash_provider_collateral(&proposal);
+                Self::remove_deal(deal_id, &proposal);
+                Self::deposit_event(Event::DealSlashed(deal_id, slashed));
+                None
+            } else if now >= proposal.end_epoch {
+                // following processDealExpired, the collaterals are unlocked
+                Self::unlock_balance(&proposal.client, proposal.client_collateral);
+                Self::unlock_balance(&proposal.provider, proposal.provider_collateral);
+                Self::remove_deal(deal_id, &proposal);
+                Self::deposit_event(Event::DealCompleted(deal_id));
+                None
+            } else {
+                state.last_updated_epoch = Some(now);
+                States::<T>::insert(deal_id, state);
+                // Slashed deals are settled at their next update
+                Some(
+                    now.saturating_add(T::DealUpdatesInterval::get().max(One::one()))
+                        .min(proposal.end_epoch),
+                )
+            }
+        }
+
+        /// Schedules a deal to be processed by the cron at `epoch`
+        fn schedule_deal(epoch: BlockNumberFor<T>, deal_id: DealId) {
+            let index = DealOpsCount::<T>::get(epoch);
+            DealOpsByEpoch::<T>::insert(epoch, index, deal_id);
+            DealOpsCount::<T>::insert(epoch, index.saturating_add(1));
+        }
+
+        /// Unlocks `amount` of the escrow of `address`
+        fn unlock_balance(address: &T::AccountId, amount: BalanceOf<T>) {
+            LockedTable::<T>::mutate(address, |locked| *locked = locked.saturating_sub(amount));
+        }
+
+        /// Moves `amount` of the locked escrow of `from` to the escrow of `to`
+        fn transfer_balance(from: &T::AccountId, to: &T::AccountId, amount: BalanceOf<T>) {
+            EscrowTable::<T>::mutate(from, |escrow| *escrow = escrow.saturating_sub(amount));
+            Self::unlock_balance(from, amount);
+            EscrowTable::<T>::mutate(to, |escrow| *escrow = escrow.saturating_add(amount));
+        }
+
+        /// Slashes the collateral of the provider of a deal from its locked escrow.
+        /// Returns the amount slashed
+        fn slash_provider_collateral(proposal: &DealProposalOf<T>) -> BalanceOf<T> {
+            let collateral = proposal.provider_collateral;
+            EscrowTable::<T>::mutate(&proposal.provider, |escrow| {
+                *escrow = escrow.saturating_sub(collateral)
+            });
+            Self::unlock_balance(&proposal.provider, collateral);
+            let (imbalance, _) = T::Currency::slash(&Self::account_id(), collateral);
+            T::Slashed::on_unbalanced(imbalance);
+            collateral
+        }
+
+        /// Removes a deal and the hash of its proposal, only called once the deal started as
+        /// proposals starting in the past can no longer be published
+        fn remove_deal(deal_id: DealId, proposal: &DealProposalOf<T>) {
+            Proposals::<T>::remove(deal_id);
+            States::<T>::remove(deal_id);
+            PendingProposals::<T>::remove(T::Hashing::hash_of(proposal));
+        }
+    }
+
+    impl<T: Config> Market for Pallet<T> {
+        type AccountId = T::AccountId;
+        type BlockNumber = BlockNumberFor<T>;
+
+        fn verify_deals_for_activation(
+            miner: &T::AccountId,
+            deal_ids: &[DealId],
+            sector_activation: BlockNumberFor<T>,
+            sector_expiration: BlockNumberFor<T>,
+            sector_size: u64,
+        ) -> Result<(DealWeight, DealWeight), DispatchError> {
+            // following VerifyDealsForActivation in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/market/market_actor.go
+            // Note: The pieces of the deals must also fit in the sector
+            let mut seen_deals = BTreeSet::new();
+            let mut deal_space: u64 = 0;
+            let mut deal_weight: DealWeight = 0;
+            let mut verified_deal_weight: DealWeight = 0;
+            for deal_id in deal_ids {
+                ensure!(seen_deals.insert(*deal_id), Error::<T>::DuplicateDeal);
+                let proposal = Proposals::<T>::get(deal_id).ok_or(Error::<T>::NoSuchDeal)?;
+                let state = States::<T>::get(deal_id).ok_or(Error::<T>::NoSuchDeal)?;
+                ensure!(&proposal.provider == miner, Error::<T>::InvalidProvider);
+                ensure!(
+                    state.sector_start_epoch.is_none(),
+                    Error::<T>::DealAlreadyActivated
+                );
+                ensure!(
+                    sector_activation <= proposal.start_epoch,
+                    Error::<T>::DealStartElapsed
+                );
+                ensure!(
+                    proposal.end_epoch <= sector_expiration,
+                    Error::<T>::DealEndsAfterSector
+                );
+                deal_space = deal_space.saturating_add(proposal.piece_size);
+                let weight = (proposal.piece_size as u128)
+                    .saturating_mul(proposal.duration().saturated_into::<u128>());
+                if proposal.verified_deal {
+                    verified_deal_weight = verified_deal_weight.saturating_add(weight);
+                } else {
+                    deal_weight = deal_weight.saturating_add(weight);
+                }
+            }
+            ensure!(deal_space <= sector_size, Error::<T>::DealsExceedSectorSize);
+            Ok((deal_weight, verified_deal_weight))
+        }
+
+        fn activate_deals(deal_ids: &[DealId], sector_activation: BlockNumberFor<T>) {
+            // following ActivateDeals in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/market/market_actor.go
+            for deal_id in deal_ids {
+                States::<T>::mutate(deal_id, |maybe_state| {
+                    if let Some(state) = maybe_state {
+                        state.sector_start_epoch = Some(sector_activation);
+                    }
+                });
+            }
+        }
+
+        fn on_sectors_terminated(
+            miner: &T::AccountId,
+            deal_ids: &[DealId],
+            epoch: BlockNumberFor<T>,
+        ) {
+            // following OnMinerSectorsTerminate in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/market/market_actor.go
+            // Note: Deals that ended or are not activated are left to the cron
+            for deal_id in deal_ids {
+                let proposal = match Proposals::<T>::get(deal_id) {
+                    Some(proposal) => proposal,
+                    None => continue,
+                };
+                if &proposal.provider != miner || proposal.end_epoch <= epoch {
+                    continue;
+                }
+                States::<T>::mutate(deal_id, |maybe_state| {
+                    if let Some(state) = maybe_state {
+                        if state.sector_start_epoch.is_some() && state.slash_epoch.is_none() {
+                            state.slash_epoch = Some(epoch);
+                        }
+                    }
+                });
+            }
+        }
+
+        fn has_escrow(address: &T::AccountId) -> bool {
+            !EscrowTable::<T>::get(address).is_zero() || !LockedTable::<T>::get(address).is_zero()
+        }
+    }
+}
+
+/// Storage deal between a client and a provider
+/// following https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/market/deal.go
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+pub struct DealProposal<AccountId, Balance, BlockNumber> {
+    /// CommP of the piece of data stored
+    pub piece_cid: Vec<u8>,
+    /// Padded size of the piece in bytes, a power of two
+    pub piece_size: u64,
+    pub client: AccountId,
+    /// Miner storing the piece
+    pub provider: AccountId,
+    /// Block the sector containing the deal must be proven by
+    pub start_epoch: BlockNumber,
+    /// Block the deal ends at
+    pub end_epoch: BlockNumber,
+    /// Payment from the client to the provider for each block of the deal
+    pub storage_price_per_epoch: Balance,
+    /// Collateral locked by the provider
+    pub provider_collateral: Balance,
+    /// Collateral locked by the client
+    pub client_collateral: Balance,
+    /// Whether the deal consumes the DataCap of the client for a higher quality multiplier
+    pub verified_deal: bool,
+}
+
+impl<AccountId, Balance, BlockNumber> DealProposal<AccountId, Balance, BlockNumber>
+where
+    Balance: AtLeast32BitUnsigned + Copy,
+    BlockNumber: AtLeast32BitUnsigned + Copy,
+{
+    /// Number of blocks the deal lasts
+    pub fn duration(&self) -> BlockNumber {
+        self.end_epoch.saturating_sub(self.start_epoch)
+    }
+
+    /// Payment from the client to the provider over the whole deal
+    pub fn total_storage_fee(&self) -> Balance {
+        self.storage_price_per_epoch.saturating_mul(
+            self.duration()
+                .saturated_into::<u128>()
+                .saturated_into::<Balance>(),
+        )
+    }
+
+    /// Funds the client locks for the deal, its payment and collateral
+    pub fn client_balance_requirement(&self) -> Balance {
+        self.client_collateral
+            .saturating_add(self.total_storage_fee())
+    }
+}
+
+/// Deal proposal signed by the client
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+pub struct ClientDealProposal<AccountId, Balance, BlockNumber, Signature> {
+    pub proposal: DealProposal<AccountId, Balance, BlockNumber>,
+    /// Signature of the client on the SCALE encoded proposal
+    pub client_signature: Signature,
+}
+
+/// State of a published deal
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Default)]
+pub struct DealState<BlockNumber> {
+    /// Block the sector containing the deal was proven at, `None` until the deal is activated
+    pub sector_start_epoch: Option<BlockNumber>,
+    /// Block the payment of the deal was last processed at
+    pub last_updated_epoch: Option<BlockNumber>,
+    /// Block the deal was slashed at, `None` unless its sector is terminated
+    pub slash_epoch: Option<BlockNumber>,
+}
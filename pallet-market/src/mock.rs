@@ -0,0 +1,152 @@
+use crate as pallet_market;
+use frame_support::parameter_types;
+use frame_system as system;
+use frame_system::EnsureRoot;
+use pallet_common::MinerAccounts;
+use sp_core::H256;
+use sp_runtime::{
+    testing::{Header, TestSignature, UintAuthorityId},
+    traits::{BlakeTwo256, IdentityLookup},
+    ModuleId,
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+    pub enum Test where
+        Block = Block,
+        NodeBlock = Block,
+        UncheckedExtrinsic = UncheckedExtrinsic,
+        {
+            System: frame_system::{Module, Call, Config, Storage, Event<T>},
+            Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
+            VerifReg: pallet_verifreg::{Module, Call, Storage, Event<T>},
+            Market: pallet_market::{Module, Call, Storage, Event<T>},
+        }
+);
+
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+    pub const SS58Prefix: u8 = 42;
+}
+
+// not sure where the Call, Event, PalletInfo comes from
+impl system::Config for Test {
+    type BaseCallFilter = ();
+    type BlockWeights = ();
+    type BlockLength = ();
+    type DbWeight = ();
+    type Origin = Origin;
+    type Call = Call;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = u64;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = Event;
+    type BlockHashCount = BlockHashCount;
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = pallet_balances::AccountData<u64>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = SS58Prefix;
+}
+
+parameter_types! {
+    pub const ExistentialDeposit: u64 = 1;
+    pub const MaxLocks: u32 = 50;
+}
+
+impl pallet_balances::Config for Test {
+    type MaxLocks = MaxLocks;
+    type Balance = u64;
+    type Event = Event;
+    type DustRemoval = ();
+    type ExistentialDeposit = ExistentialDeposit;
+    type AccountStore = System;
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    pub MinVerifiedDealSize: u64 = 1_024;
+}
+
+impl pallet_verifreg::Config for Test {
+    type Event = Event;
+    type RootOrigin = EnsureRoot<u64>;
+    type MinVerifiedDealSize = MinVerifiedDealSize;
+}
+
+pub const PROVIDER: u64 = 100;
+pub const OWNER: u64 = 101;
+pub const WORKER: u64 = 102;
+pub const CLIENT: u64 = 200;
+
+/// Single miner `PROVIDER` owned by `OWNER` with `WORKER` as worker
+pub struct MockMiners;
+
+impl MinerAccounts for MockMiners {
+    type AccountId = u64;
+
+    fn owner(miner: &u64) -> Option<u64> {
+        if *miner == PROVIDER {
+            Some(OWNER)
+        } else {
+            None
+        }
+    }
+
+    fn worker(miner: &u64) -> Option<u64> {
+        if *miner == PROVIDER {
+            Some(WORKER)
+        } else {
+            None
+        }
+    }
+
+    fn is_controlling_address(miner: &u64, address: &u64) -> bool {
+        *miner == PROVIDER && (*address == OWNER || *address == WORKER)
+    }
+}
+
+parameter_types! {
+    pub const MarketModuleId: ModuleId = ModuleId(*b"cgs/mrkt");
+    pub MaxDealDuration: u64 = 1_000;
+    pub MaxDealsPerBlock: u32 = 2;
+    pub DealUpdatesInterval: u64 = 10;
+}
+
+impl pallet_market::Config for Test {
+    type Event = Event;
+    type Currency = Balances;
+    type Miners = MockMiners;
+    type OffchainSignature = TestSignature;
+    type OffchainPublic = UintAuthorityId;
+    type ModuleId = MarketModuleId;
+    type MaxDealDuration = MaxDealDuration;
+    type MaxDealsPerBlock = MaxDealsPerBlock;
+    type DealUpdatesInterval = DealUpdatesInterval;
+    type Slashed = ();
+    type VerifiedRegistry = VerifReg;
+}
+
+/// Free balance of the client and provider owner at genesis
+pub const INITIAL_BALANCE: u64 = 100_000;
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let mut t = system::GenesisConfig::default()
+        .build_storage::<Test>()
+        .unwrap();
+    pallet_balances::GenesisConfig::<Test> {
+        balances: vec![(CLIENT, INITIAL_BALANCE), (OWNER, INITIAL_BALANCE)],
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+    t.into()
+}
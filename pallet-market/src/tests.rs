@@ -0,0 +1,585 @@
+// Imports created by construct_runtime macros are unresolved by rust analyzer
+use crate as pallet_market;
+use crate::mock::{
+    new_test_ext, Balances, Event, Market, Origin, System, Test, VerifReg, CLIENT, INITIAL_BALANCE,
+    OWNER, PROVIDER, WORKER,
+};
+use crate::{ClientDealProposal, DealProposal, DealState, Error};
+use codec::Encode;
+use frame_support::{
+    assert_noop, assert_ok,
+    traits::{Currency, OnInitialize},
+};
+use pallet_common::Market as MarketTrait;
+use sp_runtime::{testing::TestSignature, traits::Hash};
+
+// Utility functions
+//
+// Deal of 100 blocks from block 10, the client locks 1_100 and the provider 500
+fn deal_proposal(piece_cid: u8) -> DealProposal<u64, u64, u64> {
+    DealProposal {
+        piece_cid: vec![piece_cid],
+        piece_size: 2048,
+        client: CLIENT,
+        provider: PROVIDER,
+        start_epoch: 10,
+        end_epoch: 110,
+        storage_price_per_epoch: 10,
+        provider_collateral: 500,
+        client_collateral: 100,
+        verified_deal: false,
+    }
+}
+
+fn sign(proposal: DealProposal<u64, u64, u64>) -> ClientDealProposal<u64, u64, u64, TestSignature> {
+    let client_signature = TestSignature(proposal.client, proposal.encode());
+    ClientDealProposal {
+        proposal,
+        client_signature,
+    }
+}
+
+fn run_to_block(n: u64) {
+    while System::block_number() < n {
+        let block = System::block_number() + 1;
+        System::set_block_number(block);
+        Market::on_initialize(block);
+    }
+}
+
+// Deals scheduled at `epoch` that are not processed yet
+fn deal_ops(epoch: u64) -> Vec<u64> {
+    (0..Market::deal_ops_count(epoch))
+        .filter_map(|index| Market::deal_ops_by_epoch(epoch, index))
+        .collect()
+}
+
+// Publishes the deals of `piece_cids` at block 1 and activates them at block 5
+fn publish_active_deals(piece_cids: Vec<u8>) {
+    System::set_block_number(1);
+    let count = piece_cids.len() as u64;
+    add_escrow(count * 1_100, count * 500);
+    assert_ok!(Market::publish_storage_deals(
+        Origin::signed(WORKER),
+        piece_cids
+            .into_iter()
+            .map(|cid| sign(deal_proposal(cid)))
+            .collect()
+    ));
+    run_to_block(5);
+    let deal_ids: Vec<u64> = (0..count).collect();
+    Market::activate_deals(&deal_ids, 5);
+}
+
+fn add_escrow(client_amount: u64, provider_amount: u64) {
+    assert_ok!(Market::add_balance(
+        Origin::signed(CLIENT),
+        CLIENT,
+        client_amount
+    ));
+    assert_ok!(Market::add_balance(
+        Origin::signed(OWNER),
+        PROVIDER,
+        provider_amount
+    ));
+}
+
+#[test]
+fn add_balance_transfers_funds_to_escrow() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        add_escrow(2_000, 1_000);
+
+        assert_eq!(Market::escrow_table(CLIENT), 2_000);
+        assert_eq!(Market::escrow_table(PROVIDER), 1_000);
+        assert_eq!(Balances::free_balance(CLIENT), INITIAL_BALANCE - 2_000);
+        assert_eq!(Balances::free_balance(OWNER), INITIAL_BALANCE - 1_000);
+        assert_eq!(Balances::free_balance(Market::account_id()), 3_000);
+        assert_eq!(
+            System::events().pop().map(|e| e.event),
+            Some(Event::pallet_market(pallet_market::Event::BalanceAdded(
+                PROVIDER, 1_000
+            )))
+        );
+
+        assert!(Market::add_balance(Origin::signed(CLIENT), CLIENT, INITIAL_BALANCE).is_err());
+    });
+}
+
+#[test]
+fn withdraw_balance_is_capped_at_unlocked_escrow() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        add_escrow(2_000, 1_000);
+        assert_ok!(Market::publish_storage_deals(
+            Origin::signed(WORKER),
+            vec![sign(deal_proposal(1))]
+        ));
+
+        assert_ok!(Market::withdraw_balance(
+            Origin::signed(CLIENT),
+            CLIENT,
+            2_000
+        ));
+        assert_eq!(Market::escrow_table(CLIENT), 1_100);
+        assert_eq!(Balances::free_balance(CLIENT), INITIAL_BALANCE - 1_100);
+
+        // The escrow of a provider is withdrawn to its owner
+        assert_ok!(Market::withdraw_balance(
+            Origin::signed(WORKER),
+            PROVIDER,
+            1_000
+        ));
+        assert_eq!(Market::escrow_table(PROVIDER), 500);
+        assert_eq!(Balances::free_balance(OWNER), INITIAL_BALANCE - 500);
+        assert_eq!(
+            System::events().pop().map(|e| e.event),
+            Some(Event::pallet_market(
+                pallet_market::Event::BalanceWithdrawn(PROVIDER, OWNER, 500)
+            ))
+        );
+    });
+}
+
+#[test]
+fn withdraw_balance_rejects_invalid_signer() {
+    new_test_ext().execute_with(|| {
+        add_escrow(2_000, 1_000);
+
+        assert_noop!(
+            Market::withdraw_balance(Origin::signed(OWNER), CLIENT, 1_000),
+            Error::<Test>::InvalidSigner
+        );
+        assert_noop!(
+            Market::withdraw_balance(Origin::signed(CLIENT), PROVIDER, 1_000),
+            Error::<Test>::InvalidSigner
+        );
+    });
+}
+
+#[test]
+fn has_escrow_until_escrow_is_withdrawn() {
+    new_test_ext().execute_with(|| {
+        assert!(!Market::has_escrow(&PROVIDER));
+        add_escrow(2_000, 1_000);
+        assert!(Market::has_escrow(&PROVIDER));
+
+        assert_ok!(Market::withdraw_balance(
+            Origin::signed(WORKER),
+            PROVIDER,
+            1_000
+        ));
+        assert!(!Market::has_escrow(&PROVIDER));
+    });
+}
+
+#[test]
+fn publish_storage_deals_locks_funds() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        add_escrow(3_000, 1_000);
+
+        assert_ok!(Market::publish_storage_deals(
+            Origin::signed(WORKER),
+            vec![sign(deal_proposal(1)), sign(deal_proposal(2))]
+        ));
+
+        assert_eq!(Market::proposals(0), Some(deal_proposal(1)));
+        assert_eq!(Market::proposals(1), Some(deal_proposal(2)));
+        assert_eq!(Market::states(0), Some(DealState::default()));
+        assert_eq!(Market::next_deal_id(), 2);
+        assert_eq!(Market::locked_table(CLIENT), 2_200);
+        assert_eq!(Market::locked_table(PROVIDER), 1_000);
+        assert_eq!(Market::available_balance(&CLIENT), 800);
+        assert_eq!(
+            System::events().pop().map(|e| e.event),
+            Some(Event::pallet_market(pallet_market::Event::DealsPublished(
+                PROVIDER,
+                vec![0, 1]
+            )))
+        );
+    });
+}
+
+#[test]
+fn publish_storage_deals_rejects_invalid_deals() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        add_escrow(3_000, 1_000);
+
+        assert_noop!(
+            Market::publish_storage_deals(Origin::signed(WORKER), Vec::new()),
+            Error::<Test>::NoDeals
+        );
+        assert_noop!(
+            Market::publish_storage_deals(Origin::signed(CLIENT), vec![sign(deal_proposal(1))]),
+            Error::<Test>::InvalidSigner
+        );
+
+        let mut other_provider = deal_proposal(2);
+        other_provider.provider = OWNER;
+        assert_noop!(
+            Market::publish_storage_deals(
+                Origin::signed(WORKER),
+                vec![sign(deal_proposal(1)), sign(other_provider)]
+            ),
+            Error::<Test>::InvalidProvider
+        );
+
+        let mut forged = sign(deal_proposal(1));
+        forged.client_signature = TestSignature(OWNER, forged.proposal.encode());
+        assert_noop!(
+            Market::publish_storage_deals(Origin::signed(WORKER), vec![forged]),
+            Error::<Test>::InvalidSignature
+        );
+
+        let mut invalid_size = deal_proposal(1);
+        invalid_size.piece_size = 1000;
+        assert_noop!(
+            Market::publish_storage_deals(Origin::signed(WORKER), vec![sign(invalid_size)]),
+            Error::<Test>::InvalidPieceSize
+        );
+
+        let mut elapsed = deal_proposal(1);
+        elapsed.start_epoch = 1;
+        assert_noop!(
+            Market::publish_storage_deals(Origin::signed(WORKER), vec![sign(elapsed)]),
+            Error::<Test>::DealStartElapsed
+        );
+
+        let mut too_long = deal_proposal(1);
+        too_long.end_epoch = 1_011;
+        assert_noop!(
+            Market::publish_storage_deals(Origin::signed(WORKER), vec![sign(too_long)]),
+            Error::<Test>::InvalidDealDuration
+        );
+    });
+}
+
+#[test]
+fn publish_storage_deals_requires_funds_for_all_deals() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        add_escrow(2_000, 1_000);
+
+        // The client can only pay for one of the deals
+        assert_noop!(
+            Market::publish_storage_deals(
+                Origin::signed(WORKER),
+                vec![sign(deal_proposal(1)), sign(deal_proposal(2))]
+            ),
+            Error::<Test>::InsufficientFunds
+        );
+    });
+}
+
+#[test]
+fn publish_storage_deals_rejects_duplicate_deals() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        add_escrow(5_000, 2_000);
+
+        assert_noop!(
+            Market::publish_storage_deals(
+                Origin::signed(WORKER),
+                vec![sign(deal_proposal(1)), sign(deal_proposal(1))]
+            ),
+            Error::<Test>::DuplicateDeal
+        );
+        assert_ok!(Market::publish_storage_deals(
+            Origin::signed(WORKER),
+            vec![sign(deal_proposal(1))]
+        ));
+        assert_noop!(
+            Market::publish_storage_deals(Origin::signed(WORKER), vec![sign(deal_proposal(1))]),
+            Error::<Test>::DuplicateDeal
+        );
+    });
+}
+
+#[test]
+fn verify_deals_for_activation_returns_deal_weight() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        add_escrow(3_000, 1_000);
+        assert_ok!(Market::publish_storage_deals(
+            Origin::signed(WORKER),
+            vec![sign(deal_proposal(1)), sign(deal_proposal(2))]
+        ));
+
+        // Both deals of 2_048 bytes last 100 blocks
+        assert_eq!(
+            Market::verify_deals_for_activation(&PROVIDER, &[0, 1], 5, 200, 4_096),
+            Ok((409_600, 0))
+        );
+        assert_eq!(
+            Market::verify_deals_for_activation(&PROVIDER, &[], 5, 200, 4_096),
+            Ok((0, 0))
+        );
+    });
+}
+
+#[test]
+fn verify_deals_for_activation_rejects_invalid_deals() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        add_escrow(3_000, 1_000);
+        assert_ok!(Market::publish_storage_deals(
+            Origin::signed(WORKER),
+            vec![sign(deal_proposal(1)), sign(deal_proposal(2))]
+        ));
+
+        assert_eq!(
+            Market::verify_deals_for_activation(&PROVIDER, &[2], 5, 200, 4_096),
+            Err(Error::<Test>::NoSuchDeal.into())
+        );
+        assert_eq!(
+            Market::verify_deals_for_activation(&OWNER, &[0], 5, 200, 4_096),
+            Err(Error::<Test>::InvalidProvider.into())
+        );
+        assert_eq!(
+            Market::verify_deals_for_activation(&PROVIDER, &[0, 0], 5, 200, 4_096),
+            Err(Error::<Test>::DuplicateDeal.into())
+        );
+        assert_eq!(
+            Market::verify_deals_for_activation(&PROVIDER, &[0], 11, 200, 4_096),
+            Err(Error::<Test>::DealStartElapsed.into())
+        );
+        assert_eq!(
+            Market::verify_deals_for_activation(&PROVIDER, &[0], 5, 109, 4_096),
+            Err(Error::<Test>::DealEndsAfterSector.into())
+        );
+        assert_eq!(
+            Market::verify_deals_for_activation(&PROVIDER, &[0, 1], 5, 200, 2_048),
+            Err(Error::<Test>::DealsExceedSectorSize.into())
+        );
+    });
+}
+
+#[test]
+fn activate_deals_sets_sector_start_epoch() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        add_escrow(3_000, 1_000);
+        assert_ok!(Market::publish_storage_deals(
+            Origin::signed(WORKER),
+            vec![sign(deal_proposal(1)), sign(deal_proposal(2))]
+        ));
+
+        Market::activate_deals(&[0], 5);
+
+        assert_eq!(Market::states(0).unwrap().sector_start_epoch, Some(5));
+        assert_eq!(Market::states(1), Some(DealState::default()));
+        assert_eq!(
+            Market::verify_deals_for_activation(&PROVIDER, &[0], 5, 200, 4_096),
+            Err(Error::<Test>::DealAlreadyActivated.into())
+        );
+    });
+}
+
+#[test]
+fn cron_pays_providers_until_deals_complete() {
+    new_test_ext().execute_with(|| {
+        publish_active_deals(vec![1]);
+
+        // The payment is settled every 10 blocks after the start of the deal
+        run_to_block(60);
+        assert_eq!(Market::states(0).unwrap().last_updated_epoch, Some(51));
+
+        // 51 blocks of the deal are paid
+        run_to_block(61);
+        assert_eq!(Market::escrow_table(CLIENT), 590);
+        assert_eq!(Market::locked_table(CLIENT), 590);
+        assert_eq!(Market::escrow_table(PROVIDER), 1_010);
+        assert_eq!(Market::locked_table(PROVIDER), 500);
+        assert_eq!(Market::states(0).unwrap().last_updated_epoch, Some(61));
+        assert_eq!(deal_ops(71), vec![0]);
+        assert_eq!(Market::deal_ops_cursor(), (62, 0));
+
+        run_to_block(110);
+        assert_eq!(Market::escrow_table(CLIENT), 100);
+        assert_eq!(Market::locked_table(CLIENT), 0);
+        assert_eq!(Market::escrow_table(PROVIDER), 1_500);
+        assert_eq!(Market::locked_table(PROVIDER), 0);
+        assert_eq!(Market::proposals(0), None);
+        assert_eq!(Market::states(0), None);
+        assert_eq!(
+            System::events().pop().map(|e| e.event),
+            Some(Event::pallet_market(pallet_market::Event::DealCompleted(0)))
+        );
+    });
+}
+
+#[test]
+fn cron_slashes_deals_of_terminated_sectors() {
+    new_test_ext().execute_with(|| {
+        publish_active_deals(vec![1]);
+        run_to_block(30);
+
+        // Only the deals of the miner are slashed
+        Market::on_sectors_terminated(&OWNER, &[0], 30);
+        assert_eq!(Market::states(0).unwrap().slash_epoch, None);
+        Market::on_sectors_terminated(&PROVIDER, &[0], 30);
+        assert_eq!(Market::states(0).unwrap().slash_epoch, Some(30));
+
+        let issuance = Balances::total_issuance();
+        run_to_block(31);
+
+        // 20 blocks of the deal are paid and the rest is refunded to the client
+        assert_eq!(Market::escrow_table(CLIENT), 900);
+        assert_eq!(Market::locked_table(CLIENT), 0);
+        assert_eq!(Market::escrow_table(PROVIDER), 200);
+        assert_eq!(Market::locked_table(PROVIDER), 0);
+        assert_eq!(Balances::total_issuance(), issuance - 500);
+        assert_eq!(Balances::free_balance(Market::account_id()), 1_100);
+        assert_eq!(Market::states(0), None);
+        assert_eq!(
+            System::events().pop().map(|e| e.event),
+            Some(Event::pallet_market(pallet_market::Event::DealSlashed(
+                0, 500
+            )))
+        );
+    });
+}
+
+#[test]
+fn cron_keeps_deals_slashed_before_start_until_they_start() {
+    new_test_ext().execute_with(|| {
+        publish_active_deals(vec![1]);
+        Market::on_sectors_terminated(&PROVIDER, &[0], 6);
+        run_to_block(7);
+
+        // The proposal of the slashed deal can not be published again
+        assert_eq!(Market::states(0).unwrap().slash_epoch, Some(6));
+        add_escrow(1_100, 500);
+        assert_noop!(
+            Market::publish_storage_deals(Origin::signed(WORKER), vec![sign(deal_proposal(1))]),
+            Error::<Test>::DuplicateDeal
+        );
+
+        // Once the deal started the client is refunded in full
+        run_to_block(11);
+        assert_eq!(Market::states(0), None);
+        assert_eq!(Market::escrow_table(CLIENT), 2_200);
+        assert_eq!(Market::locked_table(CLIENT), 0);
+        assert_eq!(
+            System::events().pop().map(|e| e.event),
+            Some(Event::pallet_market(pallet_market::Event::DealSlashed(
+                0, 500
+            )))
+        );
+        assert_noop!(
+            Market::publish_storage_deals(Origin::signed(WORKER), vec![sign(deal_proposal(1))]),
+            Error::<Test>::DealStartElapsed
+        );
+    });
+}
+
+#[test]
+fn cron_times_out_deals_not_activated_before_start() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        add_escrow(1_100, 500);
+        let proposal = deal_proposal(1);
+        assert_ok!(Market::publish_storage_deals(
+            Origin::signed(WORKER),
+            vec![sign(proposal.clone())]
+        ));
+
+        run_to_block(10);
+        assert!(Market::states(0).is_some());
+
+        run_to_block(11);
+        assert_eq!(Market::escrow_table(CLIENT), 1_100);
+        assert_eq!(Market::locked_table(CLIENT), 0);
+        assert_eq!(Market::escrow_table(PROVIDER), 0);
+        assert_eq!(Market::locked_table(PROVIDER), 0);
+        assert_eq!(Market::proposals(0), None);
+        assert_eq!(
+            Market::pending_proposals(<Test as frame_system::Config>::Hashing::hash_of(&proposal)),
+            None
+        );
+        assert_eq!(
+            System::events().pop().map(|e| e.event),
+            Some(Event::pallet_market(pallet_market::Event::DealTimedOut(0)))
+        );
+    });
+}
+
+#[test]
+fn cron_processes_a_capped_number_of_due_deals_per_block() {
+    new_test_ext().execute_with(|| {
+        publish_active_deals(vec![1, 2, 3]);
+        let last_updated = |deal_id| Market::states(deal_id).unwrap().last_updated_epoch;
+        assert_eq!(deal_ops(11), vec![0, 1, 2]);
+
+        // Deals over the cap are deferred to the next block
+        run_to_block(11);
+        assert_eq!(last_updated(0), Some(11));
+        assert_eq!(last_updated(1), Some(11));
+        assert_eq!(last_updated(2), None);
+        assert_eq!(deal_ops(11), vec![2]);
+        assert_eq!(Market::deal_ops_cursor(), (11, 2));
+
+        // The cursor catches up with the current block
+        run_to_block(12);
+        assert_eq!(last_updated(2), Some(12));
+        assert_eq!(Market::deal_ops_count(11), 0);
+        assert_eq!(Market::deal_ops_cursor(), (13, 0));
+        assert_eq!(deal_ops(21), vec![0, 1]);
+        assert_eq!(deal_ops(22), vec![2]);
+
+        // Deals are not processed between their updates
+        run_to_block(20);
+        assert_eq!(last_updated(0), Some(11));
+        run_to_block(22);
+        assert_eq!(last_updated(0), Some(21));
+        assert_eq!(last_updated(2), Some(22));
+    });
+}
+
+#[test]
+fn publish_storage_deals_consumes_data_cap_of_verified_deals() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        add_escrow(3_000, 1_000);
+        let verified_deal = |piece_cid| DealProposal {
+            verified_deal: true,
+            ..deal_proposal(piece_cid)
+        };
+
+        assert_noop!(
+            Market::publish_storage_deals(Origin::signed(WORKER), vec![sign(verified_deal(1))]),
+            pallet_verifreg::Error::<Test>::NoSuchVerifiedClient
+        );
+
+        assert_ok!(VerifReg::add_verifier(Origin::root(), OWNER, 10_240));
+        assert_ok!(VerifReg::add_verified_client(
+            Origin::signed(OWNER),
+            CLIENT,
+            3_072
+        ));
+        assert_noop!(
+            Market::publish_storage_deals(
+                Origin::signed(WORKER),
+                vec![sign(verified_deal(1)), sign(verified_deal(2))]
+            ),
+            pallet_verifreg::Error::<Test>::InsufficientDataCap
+        );
+        assert_ok!(Market::publish_storage_deals(
+            Origin::signed(WORKER),
+            vec![sign(verified_deal(1)), sign(deal_proposal(2))]
+        ));
+        assert_eq!(VerifReg::verified_clients(CLIENT), Some(1_024));
+
+        // Only the verified deal counts as verified deal weight
+        assert_eq!(
+            Market::verify_deals_for_activation(&PROVIDER, &[0, 1], 5, 200, 4_096),
+            Ok((204_800, 204_800))
+        );
+
+        // The DataCap is restored when the deal times out
+        run_to_block(11);
+        assert_eq!(VerifReg::verified_clients(CLIENT), Some(3_072));
+    });
+}
@@ -0,0 +1,130 @@
+use codec::{Decode, Encode};
+use pallet_common::SectorNumber;
+use sp_runtime::traits::{AtLeast32BitUnsigned, SaturatedConversion, Saturating};
+use sp_std::{
+    collections::{btree_map::BTreeMap, btree_set::BTreeSet},
+    vec::Vec,
+};
+
+pub type DeadlineIndex = u64;
+pub type PartitionIndex = u64;
+
+/// A deadline is a window of the proving period in which the partitions assigned to it must be
+/// proven with a WindowPoSt
+/// following https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/miner/deadline_state.go
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Default)]
+pub struct Deadline<BlockNumber> {
+    /// Partitions of sectors assigned to this deadline
+    pub partitions: Vec<Partition<BlockNumber>>,
+    /// Partitions proven in the current proving period
+    pub post_submissions: BTreeSet<PartitionIndex>,
+}
+
+impl<BlockNumber> Deadline<BlockNumber> {
+    /// Number of sectors assigned to this deadline
+    pub fn sector_count(&self) -> usize {
+        self.partitions.iter().map(|p| p.sectors.len()).sum()
+    }
+
+    /// Adds a sector to the last partition, or a new one if it is full.
+    /// Returns the index of the partition the sector was added to
+    pub fn add_sector(
+        &mut self,
+        sector_number: SectorNumber,
+        partition_size: u64,
+    ) -> PartitionIndex {
+        match self.partitions.last_mut() {
+            Some(partition) if (partition.sectors.len() as u64) < partition_size => {
+                partition.sectors.insert(sector_number);
+            }
+            _ => {
+                let mut partition = Partition::default();
+                partition.sectors.insert(sector_number);
+                self.partitions.push(partition);
+            }
+        }
+        (self.partitions.len() - 1) as PartitionIndex
+    }
+}
+
+/// Group of sectors proven together in a deadline
+/// following https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/miner/partition_state.go
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Default)]
+pub struct Partition<BlockNumber> {
+    /// All sectors assigned to this partition
+    pub sectors: BTreeSet<SectorNumber>,
+    /// Faulty sectors and the block at which they became faulty
+    pub faults: BTreeMap<SectorNumber, BlockNumber>,
+    /// Faulty sectors declared recovered, they regain power with the next WindowPoSt
+    pub recoveries: BTreeSet<SectorNumber>,
+}
+
+impl<BlockNumber> Partition<BlockNumber> {
+    /// Sectors that are not faulty
+    pub fn active_sectors<'a>(&'a self) -> impl Iterator<Item = &'a SectorNumber> + 'a {
+        self.sectors
+            .iter()
+            .filter(move |sector_number| !self.faults.contains_key(*sector_number))
+    }
+
+    /// Sectors that must be proven in the next WindowPoSt, active and recovering sectors
+    pub fn challenged_sectors<'a>(&'a self) -> impl Iterator<Item = &'a SectorNumber> + 'a {
+        self.sectors.iter().filter(move |sector_number| {
+            !self.faults.contains_key(*sector_number) || self.recoveries.contains(*sector_number)
+        })
+    }
+}
+
+/// Sectors of a partition declared faulty or recovered by a miner
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+pub struct FaultDeclaration {
+    pub deadline: DeadlineIndex,
+    pub partition: PartitionIndex,
+    pub sectors: Vec<SectorNumber>,
+}
+
+/// Sectors of a partition terminated by a miner
+pub type TerminationDeclaration = FaultDeclaration;
+
+/// Number of sectors addressed by `declarations`, bounds the work of the declaring calls
+pub fn declared_sectors_count(declarations: &[FaultDeclaration]) -> u64 {
+    declarations
+        .iter()
+        .map(|declaration| declaration.sectors.len() as u64)
+        .sum()
+}
+
+/// Timing of a miner's deadline
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+pub struct DeadlineInfo<BlockNumber> {
+    /// Index of the deadline in the proving period
+    pub index: DeadlineIndex,
+    /// First block in which proofs can be submitted
+    pub open: BlockNumber,
+    /// First block after the deadline, where proofs are no longer accepted
+    pub close: BlockNumber,
+    /// Block the WindowPoSt challenge randomness is drawn from
+    pub challenge: BlockNumber,
+}
+
+impl<BlockNumber: AtLeast32BitUnsigned + Copy> DeadlineInfo<BlockNumber> {
+    pub fn new(
+        proving_period_start: BlockNumber,
+        index: DeadlineIndex,
+        challenge_window: BlockNumber,
+        challenge_lookback: BlockNumber,
+    ) -> Self {
+        let open = proving_period_start + challenge_window * index.saturated_into::<BlockNumber>();
+        DeadlineInfo {
+            index,
+            open,
+            close: open + challenge_window,
+            challenge: open.saturating_sub(challenge_lookback),
+        }
+    }
+
+    /// Whether proofs for this deadline can be submitted at `block`
+    pub fn is_open(&self, block: BlockNumber) -> bool {
+        self.open <= block && block < self.close
+    }
+}
@@ -6,30 +6,132 @@ mod tests;
 #[cfg(test)]
 mod mock;
 
+mod deadline;
+
 use codec::{Decode, Encode};
+use pallet_common::{DealId, DealWeight, RegisteredSealProof, SectorNumber};
 pub use sp_std::vec::Vec;
 
+pub use deadline::{
+    Deadline, DeadlineIndex, DeadlineInfo, FaultDeclaration, Partition, PartitionIndex,
+    TerminationDeclaration,
+};
 // `pallet::Module` is created by `pallet` macro
-pub use pallet::{Config, Error, Event, MinerIndex, Miners, Module, Pallet};
+pub use pallet::{
+    Config, DeadlineCron, Deadlines, Error, Event, MinerIndex, MinerStates, Miners, Module, Pallet,
+    PreCommitExpirations, PreCommittedSectors, SectorExpirations, Sectors, VestingFunds,
+};
 
 #[frame_support::pallet]
 pub mod pallet {
-    use super::{MinerControllers, MinerInfo, Vec, WorkerKeyChange};
+    use super::{
+        deadline::declared_sectors_count, Deadline, DeadlineIndex, DeadlineInfo, FaultDeclaration,
+        MinerControllers, MinerInfo, MinerState, Partition, PartitionIndex, SectorOnChainInfo,
+        SectorPreCommitInfo, SectorPreCommitOnChainInfo, TerminationDeclaration, Vec, VestingFund,
+        WorkerKeyChange,
+    };
+    use codec::Encode;
     use frame_support::pallet_prelude::{
-        ensure, Blake2_128Concat, DispatchResultWithPostInfo, Get, Hooks, IsType, PhantomData,
-        StorageMap, StorageValue,
+        ensure, Blake2_128Concat, DispatchResult, DispatchResultWithPostInfo, Get, Hooks, IsType,
+        PhantomData, StorageDoubleMap, StorageMap, StorageValue, Twox64Concat, ValueQuery,
+    };
+    use frame_support::traits::{
+        Currency, ExistenceRequirement, Imbalance, OnUnbalanced, ReservableCurrency,
     };
+    use frame_support::transactional;
+    use frame_support::weights::Weight;
     use frame_system::pallet_prelude::{ensure_signed, BlockNumberFor, OriginFor};
+    use sp_runtime::helpers_128bit::multiply_by_rational;
+    use sp_runtime::traits::{
+        BlakeTwo256, CheckedDiv, Hash, SaturatedConversion, Saturating, Zero,
+    };
+    use sp_std::collections::{btree_map::BTreeMap, btree_set::BTreeSet};
 
-    use pallet_common::{AccountIdConversion, MinerId, Power};
+    use pallet_common::{
+        AccountIdConversion, ConsensusFaultVerifier, ConsensusFaultVerifyInfo, DealId, DealWeight,
+        Market, MinerAccounts, MinerId, Power, PowerDelta, ProofVerifier, RewardDistributor,
+        SealVerifyInfo, SectorInfo, SectorNumber, WindowPoStVerifyInfo, WinningPoStSectors,
+    };
     pub type MinerAccountId<T> = <<T as Config>::Power as Power>::AccountId;
     pub type PeerId<T> = <<T as Config>::Power as Power>::PeerId;
+    pub type StoragePower<T> = <<T as Config>::Power as Power>::StoragePower;
+    pub type BalanceOf<T> =
+        <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+    pub type NegativeImbalanceOf<T> = <<T as Config>::Currency as Currency<
+        <T as frame_system::Config>::AccountId,
+    >>::NegativeImbalance;
 
     #[pallet::config]
     pub trait Config: frame_system::Config {
         type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
-        type Power: Power;
+        type Power: Power<AccountId = Self::AccountId, BlockNumber = BlockNumberFor<Self>>;
+        /// Currency the pledge collateral of miners is reserved in
+        type Currency: ReservableCurrency<Self::AccountId>;
+        /// Handler for the funds slashed from miners, burnt when set to `()`
+        type Slashed: OnUnbalanced<NegativeImbalanceOf<Self>>;
         type BlockDelay: Get<BlockNumberFor<Self>>;
+        /// Maximum number of blocks the seal randomness epoch of a pre-commit can lag behind
+        /// the block it is submitted in
+        type MaxPreCommitRandomnessLookback: Get<BlockNumberFor<Self>>;
+        /// Number of blocks after pre-commit before the interactive seal randomness is drawn,
+        /// a sector can only be proven after this delay
+        type PreCommitChallengeDelay: Get<BlockNumberFor<Self>>;
+        /// Maximum number of blocks after pre-commit in which the sector must be proven
+        type MaxProveCommitDuration: Get<BlockNumberFor<Self>>;
+        /// Verifier for the proofs submitted by miners
+        type ProofVerifier: ProofVerifier;
+        /// Number of blocks in which every active sector of a miner is proven once
+        type WPoStProvingPeriod: Get<BlockNumberFor<Self>>;
+        /// Number of deadlines a proving period is divided into
+        type WPoStPeriodDeadlines: Get<u64>;
+        /// Maximum number of sectors in a partition
+        type WPoStPartitionSectors: Get<u64>;
+        /// Number of blocks before a deadline opens that its challenge randomness is drawn from
+        type WPoStChallengeLookback: Get<BlockNumberFor<Self>>;
+        /// Reward of a block for the whole network, used to project the expected reward of
+        /// a sector from its share of the network power
+        type EpochReward: Get<BalanceOf<Self>>;
+        /// Maximum age of a sector, in blocks, accounted for in its early termination penalty
+        type TerminationLifetimeCap: Get<BlockNumberFor<Self>>;
+        /// Number of blocks a sector can stay faulty before it is terminated
+        type MaxFaultDuration: Get<BlockNumberFor<Self>>;
+        /// Maximum number of blocks between the activation and the expiration of a sector
+        type MaxSectorLifetime: Get<BlockNumberFor<Self>>;
+        /// Maximum number of declarations in a single fault, recovery or termination call
+        type MaxDeclarations: Get<u32>;
+        /// Maximum number of sectors addressed by a single fault, recovery or termination call
+        type MaxDeclaredSectors: Get<u32>;
+        /// Number of blocks of expected reward a sector pledges as collateral when proven
+        type InitialPledgeProjectionPeriod: Get<BlockNumberFor<Self>>;
+        /// Number of blocks of expected reward a faulty sector is charged at each of its deadlines
+        type FaultFeeProjectionPeriod: Get<BlockNumberFor<Self>>;
+        /// Number of blocks of expected reward deposited with a pre-commit, forfeited if the
+        /// sector is not proven in time
+        type PreCommitDepositProjectionPeriod: Get<BlockNumberFor<Self>>;
+        /// Number of blocks over which the rewards credited to a miner vest
+        type RewardVestingPeriod: Get<BlockNumberFor<Self>>;
+        /// Number of equal steps the rewards vest in over `RewardVestingPeriod`
+        type RewardVestingSteps: Get<u32>;
+        /// Market the deals stored in sectors are activated in
+        type Market: Market<AccountId = Self::AccountId, BlockNumber = BlockNumberFor<Self>>;
+        /// Quality multiplier of the committed capacity of a sector, the quality adjusted power
+        /// of a sector is its size times its weighted multiplier divided by this base
+        type QualityBaseMultiplier: Get<u64>;
+        /// Quality multiplier of the space time of deals in a sector
+        type DealWeightMultiplier: Get<u64>;
+        /// Quality multiplier of the space time of verified deals in a sector
+        type VerifiedDealWeightMultiplier: Get<u64>;
+        /// Verifier for the evidence of consensus faults
+        type ConsensusFaultVerifier: ConsensusFaultVerifier<BlockNumberFor<Self>>;
+        /// Number of block rewards of the network a miner is penalized for a consensus fault
+        type ConsensusFaultFactor: Get<u32>;
+        /// Divisor of the consensus fault penalty paid to the reporter of the fault
+        type ConsensusFaultReporterDivisor: Get<u32>;
+        /// Number of blocks a miner is ineligible to produce blocks for after a consensus fault
+        type ConsensusFaultIneligibilityDuration: Get<BlockNumberFor<Self>>;
+        /// Number of blocks after which a consensus fault can no longer be reported, following
+        /// ChainFinality in Filecoin policy
+        type ConsensusFaultMaxAge: Get<BlockNumberFor<Self>>;
     }
 
     #[pallet::pallet]
@@ -37,7 +139,33 @@ pub mod pallet {
     pub struct Pallet<T>(PhantomData<T>);
 
     #[pallet::hooks]
-    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        // Note: The weight of the deadlines scales with the sectors of their partitions
+        fn on_initialize(now: BlockNumberFor<T>) -> Weight {
+            let miners = DeadlineCron::<T>::take(now);
+            let mut deadlines_weight: Weight = 0;
+            for miner in miners.iter() {
+                deadlines_weight =
+                    deadlines_weight.saturating_add(Self::process_deadline_end(miner, now));
+            }
+            let expirations = SectorExpirations::<T>::take(now);
+            for (miner, sector_number) in expirations.iter() {
+                Self::process_sector_expiration(miner, *sector_number, now);
+            }
+            let pre_commit_expirations = PreCommitExpirations::<T>::take(now);
+            for (miner, sector_number) in pre_commit_expirations.iter() {
+                Self::process_pre_commit_expiration(miner, *sector_number, now);
+            }
+            T::DbWeight::get()
+                .reads_writes(
+                    3 + 2 * expirations.len() as Weight
+                        + 2 * pre_commit_expirations.len() as Weight,
+                    3 + 3 * expirations.len() as Weight
+                        + 3 * pre_commit_expirations.len() as Weight,
+                )
+                .saturating_add(deadlines_weight)
+        }
+    }
 
     #[pallet::storage]
     #[pallet::getter(fn miners)]
@@ -52,6 +180,94 @@ pub mod pallet {
     #[pallet::getter(fn miner_index)]
     pub type MinerIndex<T: Config> = StorageValue<_, u32>;
 
+    /// Sectors pre-committed by a miner that have not been proven yet
+    #[pallet::storage]
+    #[pallet::getter(fn pre_committed_sectors)]
+    pub type PreCommittedSectors<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        MinerAccountId<T>,
+        Blake2_128Concat,
+        SectorNumber,
+        SectorPreCommitOnChainInfo<BlockNumberFor<T>, BalanceOf<T>>,
+    >;
+
+    /// Pre-commits expiring at a block if they have not been proven, entries of proven
+    /// pre-commits are skipped
+    #[pallet::storage]
+    #[pallet::getter(fn pre_commit_expirations)]
+    pub type PreCommitExpirations<T: Config> = StorageMap<
+        _,
+        Twox64Concat,
+        BlockNumberFor<T>,
+        Vec<(MinerAccountId<T>, SectorNumber)>,
+        ValueQuery,
+    >;
+
+    /// Proven sectors of a miner
+    #[pallet::storage]
+    #[pallet::getter(fn sectors)]
+    pub type Sectors<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        MinerAccountId<T>,
+        Blake2_128Concat,
+        SectorNumber,
+        SectorOnChainInfo<BlockNumberFor<T>, BalanceOf<T>>,
+    >;
+
+    /// Sectors expiring at a block, entries of sectors that were terminated or extended since
+    /// are skipped
+    #[pallet::storage]
+    #[pallet::getter(fn sector_expirations)]
+    pub type SectorExpirations<T: Config> = StorageMap<
+        _,
+        Twox64Concat,
+        BlockNumberFor<T>,
+        Vec<(MinerAccountId<T>, SectorNumber)>,
+        ValueQuery,
+    >;
+
+    /// Proving state of a miner
+    #[pallet::storage]
+    #[pallet::getter(fn miner_states)]
+    pub type MinerStates<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        MinerAccountId<T>,
+        MinerState<BlockNumberFor<T>, BalanceOf<T>>,
+    >;
+
+    /// Locked rewards of a miner by the block they vest at, ordered by block
+    #[pallet::storage]
+    #[pallet::getter(fn vesting_funds)]
+    pub type VestingFunds<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        MinerAccountId<T>,
+        Vec<VestingFund<BlockNumberFor<T>, BalanceOf<T>>>,
+        ValueQuery,
+    >;
+
+    /// Deadlines of a miner, sectors are assigned to partitions within them
+    #[pallet::storage]
+    #[pallet::getter(fn deadlines)]
+    pub type Deadlines<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        MinerAccountId<T>,
+        Twox64Concat,
+        DeadlineIndex,
+        Deadline<BlockNumberFor<T>>,
+        ValueQuery,
+    >;
+
+    /// Miners whose current deadline closes at a block, processed in `on_initialize`
+    #[pallet::storage]
+    #[pallet::getter(fn deadline_cron)]
+    pub type DeadlineCron<T: Config> =
+        StorageMap<_, Twox64Concat, BlockNumberFor<T>, Vec<MinerAccountId<T>>, ValueQuery>;
+
     #[pallet::event]
     #[pallet::generate_deposit(pub(super) fn deposit_event)]
     #[pallet::metadata(MinerAccountId<T> = "MinerAccountId", T::AccountId = "AccountID", PeerId<T> = "PeerId")]
@@ -72,6 +288,41 @@ pub mod pallet {
         OwnerChangeRequested(MinerAccountId<T>, T::AccountId),
         /// Emits miner address and new owner address
         OwnerChanged(MinerAccountId<T>, T::AccountId),
+        /// Emits miner address and the pre-committed sector number
+        SectorPreCommitted(MinerAccountId<T>, SectorNumber),
+        /// Emits miner address and the proven sector number
+        SectorProven(MinerAccountId<T>, SectorNumber),
+        /// Emits miner address, deadline and partitions proven with a WindowPoSt
+        PoStSubmitted(MinerAccountId<T>, DeadlineIndex, Vec<PartitionIndex>),
+        /// Emits miner address and sectors marked faulty for missing their WindowPoSt
+        SectorsFaulted(MinerAccountId<T>, Vec<SectorNumber>),
+        /// Emits miner address and sectors declared faulty by the miner
+        FaultsDeclared(MinerAccountId<T>, Vec<SectorNumber>),
+        /// Emits miner address and faulty sectors declared recovered by the miner
+        RecoveriesDeclared(MinerAccountId<T>, Vec<SectorNumber>),
+        /// Emits miner address and recovered sectors that regained power with a WindowPoSt
+        SectorsRecovered(MinerAccountId<T>, Vec<SectorNumber>),
+        /// Emits miner address, terminated sectors and their early termination penalty
+        SectorsTerminated(MinerAccountId<T>, Vec<SectorNumber>, BalanceOf<T>),
+        /// Emits miner address and sector number of an expired sector
+        SectorExpired(MinerAccountId<T>, SectorNumber),
+        /// Emits miner address, sector number and new expiration of an extended sector
+        SectorExpirationExtended(MinerAccountId<T>, SectorNumber, BlockNumberFor<T>),
+        /// Emits miner address and the fee slashed from its pledge for faulty sectors
+        FaultFeeCharged(MinerAccountId<T>, BalanceOf<T>),
+        /// Emits miner address, sector number and forfeited deposit of an expired pre-commit
+        PreCommitExpired(MinerAccountId<T>, SectorNumber, BalanceOf<T>),
+        /// Emits miner address, owner address and the amount withdrawn to the owner
+        BalanceWithdrawn(MinerAccountId<T>, T::AccountId, BalanceOf<T>),
+        /// Emits miner address and the amount added to its fee debt
+        DebtIncurred(MinerAccountId<T>, BalanceOf<T>),
+        /// Emits miner address and the amount of fee debt repaid
+        DebtRepaid(MinerAccountId<T>, BalanceOf<T>),
+        /// Emits miner address, reporter address, the amount slashed from the miner for the
+        /// consensus fault and the reward paid to the reporter
+        ConsensusFaultReported(MinerAccountId<T>, T::AccountId, BalanceOf<T>, BalanceOf<T>),
+        /// Emits miner address, owner address and the remaining balance transferred to the owner
+        MinerRemoved(MinerAccountId<T>, T::AccountId, BalanceOf<T>),
     }
 
     #[pallet::error]
@@ -82,6 +333,39 @@ pub mod pallet {
         InvalidSigner,
         NoRequest,
         IneffectiveRequest,
+        SectorNumberAlreadyUsed,
+        InvalidSealRandomness,
+        SealRandomnessExpired,
+        InvalidExpiration,
+        NoSuchPreCommit,
+        ProveCommitTooEarly,
+        ProveCommitExpired,
+        InvalidProof,
+        PowerUpdateFailed,
+        InvalidDeadline,
+        DeadlineNotOpen,
+        NoSuchPartition,
+        NoPartitions,
+        PartitionAlreadyProven,
+        DeadlineImmutable,
+        NoSuchSector,
+        SectorNotFaulty,
+        SectorLifetimeTooLong,
+        SectorHasDeals,
+        SectorFaulty,
+        InsufficientFunds,
+        FeeDebtOutstanding,
+        NoDebt,
+        InvalidSealProof,
+        InvalidConsensusFault,
+        ConsensusFaultTooOld,
+        MinerHasSectors,
+        MinerHasPreCommits,
+        MinerHasLockedFunds,
+        MinerHasMarketEscrow,
+        ClaimsNotRemoved,
+        TooManyDeclarations,
+        TooManyDeclaredSectors,
     }
 
     #[pallet::call]
@@ -92,6 +376,7 @@ pub mod pallet {
             origin: OriginFor<T>,
             owner: T::AccountId,
             worker: T::AccountId,
+            seal_proof_type: RegisteredSealProof,
             peer_id: PeerId<T>,
         ) -> DispatchResultWithPostInfo {
             // following https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/miner/miner_actor.go#L97
@@ -100,7 +385,7 @@ pub mod pallet {
             //
             // This allows signer to be accounts other than owner so potential services can be
             // built to create miners for owners. Signer pays for the transaction costs and not
-            // value is staked by creating miner.
+            // value is staked by creating miner, collateral is pledged when sectors are proven.
             ensure_signed(origin)?;
 
             let mut miner_index = MinerIndex::<T>::get().unwrap_or_default();
@@ -108,15 +393,34 @@ pub mod pallet {
             let miner: MinerAccountId<T> = MinerId(miner_index).into_account();
             MinerIndex::<T>::put(miner_index);
 
-            T::Power::register_new_miner(&miner).ok_or(Error::<T>::ClaimsNotSet)?;
+            T::Power::register_new_miner(&miner, seal_proof_type)
+                .ok_or(Error::<T>::ClaimsNotSet)?;
+
+            // following assignProvingPeriodOffset, the miner index is used as offset to spread
+            // the deadlines of miners across the proving period
+            let proving_period_start = <frame_system::Module<T>>::block_number()
+                + BlockNumberFor::<T>::from(miner_index) % T::WPoStProvingPeriod::get();
+            let miner_state = MinerState {
+                proving_period_start,
+                current_deadline: 0,
+                initial_pledge: Zero::zero(),
+                pre_commit_deposits: Zero::zero(),
+                fee_debt: Zero::zero(),
+                locked_funds: Zero::zero(),
+            };
+            let deadline = Self::deadline_info(&miner_state);
+            MinerStates::<T>::insert(&miner, miner_state);
+            DeadlineCron::<T>::append(deadline.close, &miner);
 
             let miner_info = MinerInfo {
                 owner,
                 worker,
                 controllers: Vec::new(),
                 peer_id,
+                seal_proof_type,
                 pending_worker: None,
                 pending_owner: None,
+                consensus_fault_elapsed: None,
             };
 
             Miners::<T>::insert(miner.clone(), miner_info);
@@ -181,12 +485,7 @@ pub mod pallet {
             Miners::<T>::try_mutate(&miner, |maybe_miner_info| -> DispatchResultWithPostInfo {
                 let miner_info = maybe_miner_info.as_mut().ok_or(Error::<T>::NoSuchMiner)?;
                 ensure!(
-                    signer == miner_info.owner
-                        || signer == miner_info.worker
-                        || miner_info
-                            .controllers
-                            .iter()
-                            .any(|account| account == &signer),
+                    miner_info.is_controlling_address(&signer),
                     Error::<T>::InvalidSigner
                 );
                 miner_info.peer_id = new_peer_id.clone();
@@ -286,6 +585,1451 @@ pub mod pallet {
 
             Ok(().into())
         }
+
+        // Benchmark not accurate
+        #[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+        pub fn pre_commit_sector(
+            origin: OriginFor<T>,
+            miner: MinerAccountId<T>,
+            info: SectorPreCommitInfo<BlockNumberFor<T>>,
+        ) -> DispatchResultWithPostInfo {
+            // following PreCommitSector in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/miner/miner_actor.go
+            let signer = ensure_signed(origin)?;
+            let miner_info = Miners::<T>::try_get(&miner).map_err(|_| Error::<T>::NoSuchMiner)?;
+            ensure!(
+                miner_info.is_worker_or_controller(&signer),
+                Error::<T>::InvalidSigner
+            );
+            ensure!(
+                info.seal_proof == miner_info.seal_proof_type,
+                Error::<T>::InvalidSealProof
+            );
+            ensure!(
+                MinerStates::<T>::get(&miner).map_or(true, |state| state.fee_debt.is_zero()),
+                Error::<T>::FeeDebtOutstanding
+            );
+
+            let current_block = <frame_system::Module<T>>::block_number();
+            ensure!(
+                info.seal_rand_epoch < current_block,
+                Error::<T>::InvalidSealRandomness
+            );
+            ensure!(
+                info.seal_rand_epoch
+                    >= current_block.saturating_sub(T::MaxPreCommitRandomnessLookback::get()),
+                Error::<T>::SealRandomnessExpired
+            );
+            ensure!(
+                info.expiration > current_block,
+                Error::<T>::InvalidExpiration
+            );
+            // The sector is activated after this block, so its lifetime is at most this long
+            ensure!(
+                info.expiration <= current_block + T::MaxSectorLifetime::get(),
+                Error::<T>::SectorLifetimeTooLong
+            );
+            ensure!(
+                !PreCommittedSectors::<T>::contains_key(&miner, info.sector_number)
+                    && !Sectors::<T>::contains_key(&miner, info.sector_number),
+                Error::<T>::SectorNumberAlreadyUsed
+            );
+            let (deal_weight, verified_deal_weight) = T::Market::verify_deals_for_activation(
+                &miner,
+                &info.deal_ids,
+                current_block,
+                info.expiration,
+                info.seal_proof.sector_size(),
+            )?;
+            // The pledge is only reserved once the sector is proven, its estimate ensures the
+            // miner can afford it on top of the deposit
+            let (_, quality_adjusted_power) = Self::sector_power(
+                &info.seal_proof,
+                info.expiration - current_block,
+                deal_weight,
+                verified_deal_weight,
+            );
+            let expected_block_reward = Self::expected_block_reward(quality_adjusted_power);
+            let pre_commit_deposit = Self::pre_commit_deposit(expected_block_reward);
+            let initial_pledge = Self::initial_pledge(expected_block_reward);
+            // Funds vested by now are unlocked once every check passed
+            let vested = Self::vested_funds(&miner, current_block);
+            ensure!(
+                T::Currency::can_reserve(
+                    &miner,
+                    initial_pledge
+                        .saturating_add(pre_commit_deposit)
+                        .saturating_sub(vested)
+                ),
+                Error::<T>::InsufficientFunds
+            );
+
+            Self::unlock_vested_funds(&miner, current_block);
+            T::Currency::reserve(&miner, pre_commit_deposit)?;
+            MinerStates::<T>::mutate(&miner, |maybe_state| {
+                if let Some(state) = maybe_state {
+                    state.pre_commit_deposits =
+                        state.pre_commit_deposits.saturating_add(pre_commit_deposit);
+                }
+            });
+            let sector_number = info.sector_number;
+            PreCommittedSectors::<T>::insert(
+                &miner,
+                sector_number,
+                SectorPreCommitOnChainInfo {
+                    info,
+                    pre_commit_epoch: current_block,
+                    pre_commit_deposit,
+                },
+            );
+            // following the pre-commit expiry queue, the first block the sector can no longer be
+            // proven in
+            PreCommitExpirations::<T>::append(
+                current_block + T::MaxProveCommitDuration::get() + 1u32.into(),
+                (&miner, sector_number),
+            );
+            Self::deposit_event(Event::SectorPreCommitted(miner, sector_number));
+
+            Ok(().into())
+        }
+
+        // Benchmark not accurate
+        #[pallet::weight(10_000 + T::DbWeight::get().writes(3))]
+        pub fn prove_commit_sector(
+            origin: OriginFor<T>,
+            miner: MinerAccountId<T>,
+            sector_number: SectorNumber,
+            proof: Vec<u8>,
+        ) -> DispatchResultWithPostInfo {
+            // following ProveCommitSector and ConfirmSectorProofsValid in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/miner/miner_actor.go
+            // Note: Seal verification is done synchronously through `Config::ProofVerifier` instead
+            // of being batched by the power actor at the end of the block, deals are verified
+            // again as they may have been activated or have started since the pre-commit
+            let signer = ensure_signed(origin)?;
+            let miner_info = Miners::<T>::try_get(&miner).map_err(|_| Error::<T>::NoSuchMiner)?;
+            ensure!(
+                miner_info.is_worker_or_controller(&signer),
+                Error::<T>::InvalidSigner
+            );
+
+            let pre_commit = PreCommittedSectors::<T>::get(&miner, sector_number)
+                .ok_or(Error::<T>::NoSuchPreCommit)?;
+            let current_block = <frame_system::Module<T>>::block_number();
+            let interactive_epoch = pre_commit.pre_commit_epoch + T::PreCommitChallengeDelay::get();
+            ensure!(
+                current_block > interactive_epoch,
+                Error::<T>::ProveCommitTooEarly
+            );
+            ensure!(
+                current_block <= pre_commit.pre_commit_epoch + T::MaxProveCommitDuration::get(),
+                Error::<T>::ProveCommitExpired
+            );
+            ensure!(
+                pre_commit.info.expiration > current_block,
+                Error::<T>::InvalidExpiration
+            );
+
+            let info = pre_commit.info;
+            let verify_info = SealVerifyInfo {
+                seal_proof: info.seal_proof,
+                prover: miner.encode(),
+                sector_number,
+                sealed_cid: info.sealed_cid.clone(),
+                deal_ids: info.deal_ids.clone(),
+                randomness: <frame_system::Module<T>>::block_hash(info.seal_rand_epoch)
+                    .as_ref()
+                    .to_vec(),
+                interactive_randomness: <frame_system::Module<T>>::block_hash(interactive_epoch)
+                    .as_ref()
+                    .to_vec(),
+                proof,
+            };
+            ensure!(
+                T::ProofVerifier::verify_seal(&verify_info),
+                Error::<T>::InvalidProof
+            );
+
+            let (deal_weight, verified_deal_weight) = T::Market::verify_deals_for_activation(
+                &miner,
+                &info.deal_ids,
+                current_block,
+                info.expiration,
+                info.seal_proof.sector_size(),
+            )?;
+            let (raw_bytes_power, quality_adjusted_power) = Self::sector_power(
+                &info.seal_proof,
+                info.expiration - current_block,
+                deal_weight,
+                verified_deal_weight,
+            );
+            let expected_block_reward = Self::expected_block_reward(quality_adjusted_power);
+            let initial_pledge = Self::initial_pledge(expected_block_reward);
+            // The pre-commit deposit funds the pledge, only the difference is reserved
+            let pre_commit_deposit = pre_commit.pre_commit_deposit;
+            let pledge_top_up = initial_pledge.saturating_sub(pre_commit_deposit);
+            // Funds vested by now are unlocked once every check passed
+            ensure!(
+                T::Currency::can_reserve(
+                    &miner,
+                    pledge_top_up.saturating_sub(Self::vested_funds(&miner, current_block))
+                ),
+                Error::<T>::InsufficientFunds
+            );
+            let deadline = Self::select_deadline(&miner)?;
+
+            // Every other check is done above, nothing is written if the power update fails
+            T::Power::update_claim(
+                miner.clone(),
+                PowerDelta::Increase(raw_bytes_power),
+                PowerDelta::Increase(quality_adjusted_power),
+            )
+            .ok_or(Error::<T>::PowerUpdateFailed)?;
+
+            Self::unlock_vested_funds(&miner, current_block);
+            let partition = Deadlines::<T>::mutate(&miner, deadline, |deadline_state| {
+                deadline_state.add_sector(sector_number, T::WPoStPartitionSectors::get())
+            });
+            T::Market::activate_deals(&info.deal_ids, current_block);
+            // The top up cannot fail to be reserved as `can_reserve` was checked above
+            let _ = T::Currency::reserve(&miner, pledge_top_up);
+            T::Currency::unreserve(&miner, pre_commit_deposit.saturating_sub(initial_pledge));
+            MinerStates::<T>::mutate(&miner, |maybe_state| {
+                if let Some(state) = maybe_state {
+                    state.pre_commit_deposits =
+                        state.pre_commit_deposits.saturating_sub(pre_commit_deposit);
+                    state.initial_pledge = state.initial_pledge.saturating_add(initial_pledge);
+                }
+            });
+            PreCommittedSectors::<T>::remove(&miner, sector_number);
+            SectorExpirations::<T>::append(info.expiration, (&miner, sector_number));
+            Sectors::<T>::insert(
+                &miner,
+                sector_number,
+                SectorOnChainInfo {
+                    sector_number,
+                    seal_proof: info.seal_proof,
+                    sealed_cid: info.sealed_cid,
+                    deal_ids: info.deal_ids,
+                    activation: current_block,
+                    expiration: info.expiration,
+                    deal_weight,
+                    verified_deal_weight,
+                    deadline,
+                    partition,
+                    expected_block_reward,
+                    initial_pledge,
+                },
+            );
+            Self::deposit_event(Event::SectorProven(miner, sector_number));
+
+            Ok(().into())
+        }
+
+        // Benchmark not accurate
+        #[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+        pub fn submit_windowed_post(
+            origin: OriginFor<T>,
+            miner: MinerAccountId<T>,
+            deadline: DeadlineIndex,
+            partitions: Vec<PartitionIndex>,
+            proof: Vec<u8>,
+        ) -> DispatchResultWithPostInfo {
+            // following SubmitWindowedPoSt in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/miner/miner_actor.go
+            let signer = ensure_signed(origin)?;
+            let miner_info = Miners::<T>::try_get(&miner).map_err(|_| Error::<T>::NoSuchMiner)?;
+            ensure!(
+                miner_info.is_worker_or_controller(&signer),
+                Error::<T>::InvalidSigner
+            );
+
+            let miner_state = MinerStates::<T>::get(&miner).ok_or(Error::<T>::NoSuchMiner)?;
+            let deadline_info = Self::deadline_info(&miner_state);
+            ensure!(deadline_info.index == deadline, Error::<T>::InvalidDeadline);
+            ensure!(
+                deadline_info.is_open(<frame_system::Module<T>>::block_number()),
+                Error::<T>::DeadlineNotOpen
+            );
+            ensure!(!partitions.is_empty(), Error::<T>::NoPartitions);
+
+            let mut deadline_state = Deadlines::<T>::get(&miner, deadline);
+            let mut challenged_sectors = Vec::new();
+            for partition_index in partitions.iter() {
+                let partition = deadline_state
+                    .partitions
+                    .get(*partition_index as usize)
+                    .ok_or(Error::<T>::NoSuchPartition)?;
+                ensure!(
+                    deadline_state.post_submissions.insert(*partition_index),
+                    Error::<T>::PartitionAlreadyProven
+                );
+                for sector_number in partition.challenged_sectors() {
+                    if let Some(sector) = Sectors::<T>::get(&miner, sector_number) {
+                        challenged_sectors.push(SectorInfo {
+                            seal_proof: sector.seal_proof,
+                            sector_number: sector.sector_number,
+                            sealed_cid: sector.sealed_cid,
+                        });
+                    }
+                }
+            }
+
+            let verify_info = WindowPoStVerifyInfo {
+                prover: miner.encode(),
+                randomness: <frame_system::Module<T>>::block_hash(deadline_info.challenge)
+                    .as_ref()
+                    .to_vec(),
+                challenged_sectors,
+                proof,
+            };
+            ensure!(
+                T::ProofVerifier::verify_window_post(&verify_info),
+                Error::<T>::InvalidProof
+            );
+            Self::unlock_vested_funds(&miner, <frame_system::Module<T>>::block_number());
+
+            // following recoverActivePower, recovering sectors of the proven partitions are no
+            // longer faulty and regain their power
+            let mut recovered_sectors = Vec::new();
+            for partition_index in partitions.iter() {
+                let partition = &mut deadline_state.partitions[*partition_index as usize];
+                for sector_number in partition.recoveries.iter() {
+                    partition.faults.remove(sector_number);
+                }
+                recovered_sectors.extend(partition.recoveries.iter());
+                partition.recoveries.clear();
+            }
+
+            Deadlines::<T>::insert(&miner, deadline, deadline_state);
+            Self::deposit_event(Event::PoStSubmitted(miner.clone(), deadline, partitions));
+            if !recovered_sectors.is_empty() {
+                Self::add_sectors_power(&miner, &recovered_sectors);
+                Self::deposit_event(Event::SectorsRecovered(miner, recovered_sectors));
+            }
+
+            Ok(().into())
+        }
+
+        // Benchmark not accurate
+        #[pallet::weight(10_000 + T::DbWeight::get().writes(
+            2 + faults.len() as u64 + declared_sectors_count(faults)
+        ))]
+        pub fn declare_faults(
+            origin: OriginFor<T>,
+            miner: MinerAccountId<T>,
+            faults: Vec<FaultDeclaration>,
+        ) -> DispatchResultWithPostInfo {
+            // following DeclareFaults in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/miner/miner_actor.go
+            let signer = ensure_signed(origin)?;
+            let current_block = <frame_system::Module<T>>::block_number();
+            let (mut deadlines, declared_sectors) =
+                Self::load_declared_sectors(&miner, &signer, &faults)?;
+            Self::unlock_vested_funds(&miner, current_block);
+
+            let mut new_faults = Vec::new();
+            for declaration in faults.iter() {
+                let partition = deadlines
+                    .get_mut(&declaration.deadline)
+                    .and_then(|d| d.partitions.get_mut(declaration.partition as usize))
+                    .ok_or(Error::<T>::NoSuchPartition)?;
+                for sector_number in declaration.sectors.iter() {
+                    // Declaring a fault cancels any pending recovery of the sector
+                    partition.recoveries.remove(sector_number);
+                    if !partition.faults.contains_key(sector_number) {
+                        partition.faults.insert(*sector_number, current_block);
+                        new_faults.push(*sector_number);
+                    }
+                }
+            }
+
+            for (deadline, deadline_state) in deadlines {
+                Deadlines::<T>::insert(&miner, deadline, deadline_state);
+            }
+            Self::remove_sectors_power(&miner, &new_faults);
+            Self::deposit_event(Event::FaultsDeclared(miner, declared_sectors));
+
+            Ok(().into())
+        }
+
+        // Benchmark not accurate
+        #[pallet::weight(10_000 + T::DbWeight::get().writes(
+            1 + recoveries.len() as u64 + declared_sectors_count(recoveries)
+        ))]
+        pub fn declare_faults_recovered(
+            origin: OriginFor<T>,
+            miner: MinerAccountId<T>,
+            recoveries: Vec<FaultDeclaration>,
+        ) -> DispatchResultWithPostInfo {
+            // following DeclareFaultsRecovered in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/miner/miner_actor.go
+            let signer = ensure_signed(origin)?;
+            let (mut deadlines, declared_sectors) =
+                Self::load_declared_sectors(&miner, &signer, &recoveries)?;
+
+            for declaration in recoveries.iter() {
+                let partition = deadlines
+                    .get_mut(&declaration.deadline)
+                    .and_then(|d| d.partitions.get_mut(declaration.partition as usize))
+                    .ok_or(Error::<T>::NoSuchPartition)?;
+                for sector_number in declaration.sectors.iter() {
+                    ensure!(
+                        partition.faults.contains_key(sector_number),
+                        Error::<T>::SectorNotFaulty
+                    );
+                    partition.recoveries.insert(*sector_number);
+                }
+            }
+            Self::unlock_vested_funds(&miner, <frame_system::Module<T>>::block_number());
+
+            for (deadline, deadline_state) in deadlines {
+                Deadlines::<T>::insert(&miner, deadline, deadline_state);
+            }
+            Self::deposit_event(Event::RecoveriesDeclared(miner, declared_sectors));
+
+            Ok(().into())
+        }
+
+        // Benchmark not accurate
+        #[pallet::weight(10_000 + T::DbWeight::get().writes(
+            3 + terminations.len() as u64 + 4 * declared_sectors_count(terminations)
+        ))]
+        pub fn terminate_sectors(
+            origin: OriginFor<T>,
+            miner: MinerAccountId<T>,
+            terminations: Vec<TerminationDeclaration>,
+        ) -> DispatchResultWithPostInfo {
+            // following TerminateSectors in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/miner/miner_actor.go
+            let signer = ensure_signed(origin)?;
+            let current_block = <frame_system::Module<T>>::block_number();
+            let (mut deadlines, _) = Self::load_declared_sectors(&miner, &signer, &terminations)?;
+            Self::unlock_vested_funds(&miner, current_block);
+
+            let mut terminated_sectors = Vec::new();
+            let mut penalty = BalanceOf::<T>::zero();
+            for declaration in terminations.iter() {
+                let partition = deadlines
+                    .get_mut(&declaration.deadline)
+                    .and_then(|d| d.partitions.get_mut(declaration.partition as usize))
+                    .ok_or(Error::<T>::NoSuchPartition)?;
+                let (sectors, sectors_penalty) = Self::terminate_partition_sectors(
+                    &miner,
+                    partition,
+                    &declaration.sectors,
+                    current_block,
+                );
+                terminated_sectors.extend(sectors);
+                penalty = penalty.saturating_add(sectors_penalty);
+            }
+
+            for (deadline, deadline_state) in deadlines {
+                Deadlines::<T>::insert(&miner, deadline, deadline_state);
+            }
+            Self::deposit_event(Event::SectorsTerminated(miner, terminated_sectors, penalty));
+
+            Ok(().into())
+        }
+
+        // Benchmark not accurate
+        #[pallet::weight(10_000 + T::DbWeight::get().writes(2))]
+        pub fn extend_sector_expiration(
+            origin: OriginFor<T>,
+            miner: MinerAccountId<T>,
+            sector_number: SectorNumber,
+            new_expiration: BlockNumberFor<T>,
+        ) -> DispatchResultWithPostInfo {
+            // following ExtendSectorExpiration in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/miner/miner_actor.go
+            // Note: Only committed capacity sectors can be extended as the power of a sector
+            // does not depend on its duration until deal weights are accounted for
+            let signer = ensure_signed(origin)?;
+            let miner_info = Miners::<T>::try_get(&miner).map_err(|_| Error::<T>::NoSuchMiner)?;
+            ensure!(
+                miner_info.is_worker_or_controller(&signer),
+                Error::<T>::InvalidSigner
+            );
+
+            let mut sector =
+                Sectors::<T>::get(&miner, sector_number).ok_or(Error::<T>::NoSuchSector)?;
+            ensure!(sector.deal_ids.is_empty(), Error::<T>::SectorHasDeals);
+            let is_faulty = Deadlines::<T>::get(&miner, sector.deadline)
+                .partitions
+                .get(sector.partition as usize)
+                .map_or(false, |partition| {
+                    partition.faults.contains_key(&sector_number)
+                });
+            ensure!(!is_faulty, Error::<T>::SectorFaulty);
+            ensure!(
+                new_expiration > sector.expiration,
+                Error::<T>::InvalidExpiration
+            );
+            ensure!(
+                new_expiration.saturating_sub(sector.activation) <= T::MaxSectorLifetime::get(),
+                Error::<T>::SectorLifetimeTooLong
+            );
+
+            Self::unlock_vested_funds(&miner, <frame_system::Module<T>>::block_number());
+            // The entry at the previous expiration is skipped as it no longer matches the sector
+            sector.expiration = new_expiration;
+            Sectors::<T>::insert(&miner, sector_number, sector);
+            SectorExpirations::<T>::append(new_expiration, (&miner, sector_number));
+            Self::deposit_event(Event::SectorExpirationExtended(
+                miner,
+                sector_number,
+                new_expiration,
+            ));
+
+            Ok(().into())
+        }
+
+        // Benchmark not accurate
+        #[pallet::weight(10_000 + T::DbWeight::get().writes(2))]
+        #[transactional]
+        pub fn withdraw_balance(
+            origin: OriginFor<T>,
+            miner: MinerAccountId<T>,
+            amount: BalanceOf<T>,
+        ) -> DispatchResultWithPostInfo {
+            // following WithdrawBalance in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/miner/miner_actor.go
+            // Note: The amount withdrawn is capped at the available balance. Vested funds must be
+            // unlocked before the transfer, the call is transactional so nothing is written if
+            // the transfer fails
+            let signer = ensure_signed(origin)?;
+            let miner_info = Miners::<T>::try_get(&miner).map_err(|_| Error::<T>::NoSuchMiner)?;
+            ensure!(signer == miner_info.owner, Error::<T>::InvalidSigner);
+            ensure!(
+                MinerStates::<T>::get(&miner)
+                    .ok_or(Error::<T>::NoSuchMiner)?
+                    .fee_debt
+                    .is_zero(),
+                Error::<T>::FeeDebtOutstanding
+            );
+
+            Self::unlock_vested_funds(&miner, <frame_system::Module<T>>::block_number());
+            let miner_state = MinerStates::<T>::get(&miner).ok_or(Error::<T>::NoSuchMiner)?;
+            let amount = amount.min(Self::available_balance(&miner, &miner_state));
+            T::Currency::transfer(
+                &miner,
+                &miner_info.owner,
+                amount,
+                ExistenceRequirement::AllowDeath,
+            )?;
+            Self::deposit_event(Event::BalanceWithdrawn(miner, miner_info.owner, amount));
+
+            Ok(().into())
+        }
+
+        // Benchmark not accurate
+        #[pallet::weight(10_000 + T::DbWeight::get().writes(2))]
+        pub fn repay_debt(
+            origin: OriginFor<T>,
+            miner: MinerAccountId<T>,
+        ) -> DispatchResultWithPostInfo {
+            // following RepayDebt in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/miner/miner_actor.go
+            // Note: The debt is repaid from the free balance of the miner account, funds can be
+            // transferred to it beforehand. Miners without debt are rejected
+            let signer = ensure_signed(origin)?;
+            let miner_info = Miners::<T>::try_get(&miner).map_err(|_| Error::<T>::NoSuchMiner)?;
+            ensure!(
+                miner_info.is_controlling_address(&signer),
+                Error::<T>::InvalidSigner
+            );
+            let miner_state = MinerStates::<T>::get(&miner).ok_or(Error::<T>::NoSuchMiner)?;
+            ensure!(!miner_state.fee_debt.is_zero(), Error::<T>::NoDebt);
+
+            Self::unlock_vested_funds(&miner, <frame_system::Module<T>>::block_number());
+            let mut miner_state = MinerStates::<T>::get(&miner).ok_or(Error::<T>::NoSuchMiner)?;
+            let amount = miner_state.fee_debt.min(T::Currency::free_balance(&miner));
+            let (imbalance, not_slashed) = T::Currency::slash(&miner, amount);
+            T::Slashed::on_unbalanced(imbalance);
+            let repaid = amount - not_slashed;
+            miner_state.fee_debt -= repaid;
+            MinerStates::<T>::insert(&miner, miner_state);
+            Self::deposit_event(Event::DebtRepaid(miner, repaid));
+
+            Ok(().into())
+        }
+
+        // Benchmark not accurate
+        #[pallet::weight(10_000 + T::DbWeight::get().writes(4))]
+        pub fn report_consensus_fault(
+            origin: OriginFor<T>,
+            miner: MinerAccountId<T>,
+            header1: Vec<u8>,
+            header2: Vec<u8>,
+            header_extra: Vec<u8>,
+        ) -> DispatchResultWithPostInfo {
+            // following ReportConsensusFault in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/miner/miner_actor.go
+            // Note: The headers must be signed by the current worker of the miner. The penalty is
+            // slashed from the pledge, then from the free balance and the reporter is paid its
+            // share of the funds actually slashed, the rest is handled by `Slashed`
+            let reporter = ensure_signed(origin)?;
+            let mut miner_info =
+                Miners::<T>::try_get(&miner).map_err(|_| Error::<T>::NoSuchMiner)?;
+            let fault =
+                T::ConsensusFaultVerifier::verify_consensus_fault(&ConsensusFaultVerifyInfo {
+                    miner: miner.encode(),
+                    worker: miner_info.worker.encode(),
+                    header1,
+                    header2,
+                    header_extra,
+                })
+                .ok_or(Error::<T>::InvalidConsensusFault)?;
+            let current_block = <frame_system::Module<T>>::block_number();
+            ensure!(
+                fault.epoch < current_block,
+                Error::<T>::InvalidConsensusFault
+            );
+            ensure!(
+                current_block - fault.epoch <= T::ConsensusFaultMaxAge::get(),
+                Error::<T>::ConsensusFaultTooOld
+            );
+            // A fault is only penalized once, faults before the end of the last exclusion period
+            // are rejected
+            if let Some(elapsed) = miner_info.consensus_fault_elapsed {
+                ensure!(fault.epoch >= elapsed, Error::<T>::ConsensusFaultTooOld);
+            }
+
+            let penalty = T::EpochReward::get()
+                .saturating_mul(T::ConsensusFaultFactor::get().saturated_into::<BalanceOf<T>>());
+            let (from_pledge, not_covered) = Self::take_pledge(&miner, penalty);
+            let slashed = from_pledge.merge(Self::take_free_balance(&miner, not_covered));
+            let slashed_amount = slashed.peek();
+            let reward = penalty
+                .checked_div(
+                    &T::ConsensusFaultReporterDivisor::get().saturated_into::<BalanceOf<T>>(),
+                )
+                .unwrap_or_else(Zero::zero)
+                .min(slashed_amount);
+            // Nothing is paid if the reward is below the existential deposit of a new account,
+            // the deposit is offset by the slashed funds so the issuance is unchanged
+            let paid = T::Currency::deposit_creating(&reporter, reward);
+            let reward_paid = paid.peek();
+            let (reward_funds, remainder) = slashed.split(reward_paid);
+            let _ = reward_funds.offset(paid);
+            T::Slashed::on_unbalanced(remainder);
+
+            let elapsed =
+                current_block.saturating_add(T::ConsensusFaultIneligibilityDuration::get());
+            miner_info.consensus_fault_elapsed = Some(elapsed);
+            Miners::<T>::insert(&miner, miner_info);
+            T::Power::suspend_eligibility(&miner, elapsed);
+            Self::deposit_event(Event::ConsensusFaultReported(
+                miner,
+                reporter,
+                slashed_amount,
+                reward_paid,
+            ));
+
+            Ok(().into())
+        }
+
+        // Benchmark not accurate
+        #[pallet::weight(10_000 + T::DbWeight::get().writes(6))]
+        #[transactional]
+        pub fn remove_miner(
+            origin: OriginFor<T>,
+            miner: MinerAccountId<T>,
+        ) -> DispatchResultWithPostInfo {
+            // following the deletion of miner actors in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/power/power_actor.go
+            // Note: Miners can only be removed by their owner once all sectors are terminated or
+            // expired, all pre-commits are proven or expired and all funds are unlocked. The
+            // remaining balance is transferred to the owner and the claims are removed with the
+            // `Power::deregister_miner` method, only once every check passed. The transfer can
+            // still fail, e.g. on a lock of the miner account, the call is transactional so
+            // nothing is written then
+            let signer = ensure_signed(origin)?;
+            let miner_info = Miners::<T>::try_get(&miner).map_err(|_| Error::<T>::NoSuchMiner)?;
+            ensure!(signer == miner_info.owner, Error::<T>::InvalidSigner);
+            ensure!(
+                Sectors::<T>::iter_prefix_values(&miner).next().is_none(),
+                Error::<T>::MinerHasSectors
+            );
+            ensure!(
+                PreCommittedSectors::<T>::iter_prefix_values(&miner)
+                    .next()
+                    .is_none(),
+                Error::<T>::MinerHasPreCommits
+            );
+            let miner_state = MinerStates::<T>::get(&miner).ok_or(Error::<T>::NoSuchMiner)?;
+            ensure!(
+                miner_state.fee_debt.is_zero(),
+                Error::<T>::FeeDebtOutstanding
+            );
+            // Locked funds must all have vested, they are unlocked below
+            let now = <frame_system::Module<T>>::block_number();
+            ensure!(
+                miner_state.initial_pledge.is_zero()
+                    && miner_state.pre_commit_deposits.is_zero()
+                    && VestingFunds::<T>::get(&miner)
+                        .iter()
+                        .all(|fund| fund.epoch <= now),
+                Error::<T>::MinerHasLockedFunds
+            );
+            // The escrow of the miner could no longer be withdrawn from the market once it is
+            // removed
+            ensure!(
+                !T::Market::has_escrow(&miner),
+                Error::<T>::MinerHasMarketEscrow
+            );
+            let claim = T::Power::claim(&miner).ok_or(Error::<T>::ClaimsNotRemoved)?;
+            ensure!(
+                claim.raw_bytes_power.is_zero() && claim.quality_adjusted_power.is_zero(),
+                Error::<T>::ClaimsNotRemoved
+            );
+
+            Self::unlock_vested_funds(&miner, now);
+            let balance = T::Currency::free_balance(&miner);
+            T::Currency::transfer(
+                &miner,
+                &miner_info.owner,
+                balance,
+                ExistenceRequirement::AllowDeath,
+            )?;
+            // Can not fail, the miner is registered and claims no power
+            let _ = T::Power::deregister_miner(&miner);
+
+            // Entries of the miner left in `DeadlineCron` are skipped once its state is removed
+            Miners::<T>::remove(&miner);
+            MinerStates::<T>::remove(&miner);
+            VestingFunds::<T>::remove(&miner);
+            Deadlines::<T>::remove_prefix(&miner);
+            Self::deposit_event(Event::MinerRemoved(miner, miner_info.owner, balance));
+
+            Ok(().into())
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        /// Timing of the miner's current deadline
+        pub fn current_deadline(
+            miner: &MinerAccountId<T>,
+        ) -> Option<DeadlineInfo<BlockNumberFor<T>>> {
+            MinerStates::<T>::get(miner).map(|state| Self::deadline_info(&state))
+        }
+
+        /// Rewards of the miner still locked and rewards vested but not yet unlocked, at the
+        /// current block
+        pub fn vesting_summary(miner: &MinerAccountId<T>) -> (BalanceOf<T>, BalanceOf<T>) {
+            let now = <frame_system::Module<T>>::block_number();
+            VestingFunds::<T>::get(miner).iter().fold(
+                (Zero::zero(), Zero::zero()),
+                |(locked, vested), fund| {
+                    if fund.epoch <= now {
+                        (locked, vested.saturating_add(fund.amount))
+                    } else {
+                        (locked.saturating_add(fund.amount), vested)
+                    }
+                },
+            )
+        }
+
+        /// Locks `amount` of the miner's free balance, credited as reward, and schedules it to
+        /// vest in `RewardVestingSteps` equal steps over `RewardVestingPeriod`
+        pub fn add_locked_funds(miner: &MinerAccountId<T>, amount: BalanceOf<T>) -> DispatchResult {
+            // following AddLockedFunds in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/miner/vesting_state.go
+            ensure!(
+                MinerStates::<T>::contains_key(miner),
+                Error::<T>::NoSuchMiner
+            );
+            T::Currency::reserve(miner, amount)?;
+
+            let now = <frame_system::Module<T>>::block_number();
+            let steps = T::RewardVestingSteps::get().max(1);
+            let period = T::RewardVestingPeriod::get();
+            VestingFunds::<T>::mutate(miner, |funds| {
+                let mut scheduled = BalanceOf::<T>::zero();
+                for step in 1..=steps {
+                    let epoch = now
+                        + period * BlockNumberFor::<T>::from(step)
+                            / BlockNumberFor::<T>::from(steps);
+                    // Cumulative amounts are scheduled so the rounding of each step is not lost
+                    let vested = multiply_by_rational(
+                        amount.saturated_into::<u128>(),
+                        step as u128,
+                        steps as u128,
+                    )
+                    .map(|vested| vested.saturated_into::<BalanceOf<T>>())
+                    .unwrap_or(amount);
+                    let step_amount = vested.saturating_sub(scheduled);
+                    scheduled = vested;
+                    if step_amount.is_zero() {
+                        continue;
+                    }
+                    match funds.binary_search_by_key(&epoch, |fund| fund.epoch) {
+                        Ok(index) => {
+                            funds[index].amount = funds[index].amount.saturating_add(step_amount)
+                        }
+                        Err(index) => funds.insert(
+                            index,
+                            VestingFund {
+                                epoch,
+                                amount: step_amount,
+                            },
+                        ),
+                    }
+                }
+            });
+            MinerStates::<T>::mutate(miner, |maybe_state| {
+                if let Some(state) = maybe_state {
+                    state.locked_funds = state.locked_funds.saturating_add(amount);
+                }
+            });
+
+            Ok(())
+        }
+
+        fn deadline_info(
+            state: &MinerState<BlockNumberFor<T>, BalanceOf<T>>,
+        ) -> DeadlineInfo<BlockNumberFor<T>> {
+            let challenge_window = T::WPoStProvingPeriod::get()
+                / T::WPoStPeriodDeadlines::get().saturated_into::<BlockNumberFor<T>>();
+            DeadlineInfo::new(
+                state.proving_period_start,
+                state.current_deadline,
+                challenge_window,
+                T::WPoStChallengeLookback::get(),
+            )
+        }
+
+        /// Deadline a newly proven sector is assigned to, the one with the fewest sectors
+        /// excluding the currently open deadline so the sector is not challenged before it can
+        /// be proven
+        fn select_deadline(miner: &MinerAccountId<T>) -> Result<DeadlineIndex, Error<T>> {
+            // following assignSectorsToDeadlines in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/miner/deadline_assignment.go
+            let current_deadline = MinerStates::<T>::get(miner)
+                .ok_or(Error::<T>::NoSuchMiner)?
+                .current_deadline;
+            let deadline_count = T::WPoStPeriodDeadlines::get();
+            (0..deadline_count)
+                .filter(|index| deadline_count == 1 || *index != current_deadline)
+                .min_by_key(|index| Deadlines::<T>::get(miner, index).sector_count())
+                .ok_or(Error::<T>::InvalidDeadline)
+        }
+
+        /// Raw bytes and quality adjusted power of a sector active for `duration` blocks with
+        /// deals of `deal_weight` and verified deals of `verified_deal_weight`
+        fn sector_power(
+            seal_proof: &RegisteredSealProof,
+            duration: BlockNumberFor<T>,
+            deal_weight: DealWeight,
+            verified_deal_weight: DealWeight,
+        ) -> (StoragePower<T>, StoragePower<T>) {
+            // following QAPowerForWeight in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/miner/policy.go
+            let sector_size = seal_proof.sector_size() as u128;
+            let duration = duration.saturated_into::<u128>();
+            let base_multiplier = T::QualityBaseMultiplier::get() as u128;
+            let sector_space_time = sector_size.saturating_mul(duration);
+            let verified_space_time = verified_deal_weight.min(sector_space_time);
+            let deal_space_time = deal_weight.min(sector_space_time - verified_space_time);
+            let weighted_space_time = (sector_space_time - deal_space_time - verified_space_time)
+                .saturating_mul(base_multiplier)
+                .saturating_add(
+                    deal_space_time.saturating_mul(T::DealWeightMultiplier::get() as u128),
+                )
+                .saturating_add(
+                    verified_space_time
+                        .saturating_mul(T::VerifiedDealWeightMultiplier::get() as u128),
+                );
+            // The sector size cancels out of the average multiplier over the sector space time
+            let quality_adjusted_power = weighted_space_time
+                .checked_div(duration.saturating_mul(base_multiplier))
+                .unwrap_or(sector_size);
+            (
+                sector_size.saturated_into(),
+                quality_adjusted_power.saturated_into(),
+            )
+        }
+
+        /// Share of the network block reward a new sector with `quality_adjusted_power` is
+        /// expected to earn, projected from the network power once the sector is added
+        fn expected_block_reward(quality_adjusted_power: StoragePower<T>) -> BalanceOf<T> {
+            let total_power = T::Power::total_power()
+                .quality_adjusted_power
+                .saturating_add(quality_adjusted_power);
+            if total_power.is_zero() {
+                return BalanceOf::<T>::zero();
+            }
+            multiply_by_rational(
+                T::EpochReward::get().saturated_into::<u128>(),
+                quality_adjusted_power.saturated_into::<u128>(),
+                total_power.saturated_into::<u128>(),
+            )
+            .map(|reward| reward.saturated_into::<BalanceOf<T>>())
+            .unwrap_or_else(|_| BalanceOf::<T>::zero())
+        }
+
+        /// Deposit reserved with the pre-commit of a sector with the given expected block reward
+        fn pre_commit_deposit(expected_block_reward: BalanceOf<T>) -> BalanceOf<T> {
+            // following PreCommitDepositForPower in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/miner/monies.go
+            expected_block_reward.saturating_mul(
+                T::PreCommitDepositProjectionPeriod::get().saturated_into::<BalanceOf<T>>(),
+            )
+        }
+
+        /// Collateral pledged by a sector with the given expected block reward
+        fn initial_pledge(expected_block_reward: BalanceOf<T>) -> BalanceOf<T> {
+            // following InitialPledgeForPower in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/miner/monies.go
+            // Note: Only the projected reward is pledged, without a share of the circulating supply
+            expected_block_reward.saturating_mul(
+                T::InitialPledgeProjectionPeriod::get().saturated_into::<BalanceOf<T>>(),
+            )
+        }
+
+        /// Fee charged for a faulty sector at each of its deadlines
+        fn fault_fee(sector: &SectorOnChainInfo<BlockNumberFor<T>, BalanceOf<T>>) -> BalanceOf<T> {
+            // following PledgePenaltyForContinuedFault in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/miner/monies.go
+            sector
+                .expected_block_reward
+                .saturating_mul(T::FaultFeeProjectionPeriod::get().saturated_into::<BalanceOf<T>>())
+        }
+
+        /// Releases up to `amount` of the miner's pledge back to its free balance
+        fn release_pledge(miner: &MinerAccountId<T>, amount: BalanceOf<T>) {
+            MinerStates::<T>::mutate(miner, |maybe_state| {
+                if let Some(state) = maybe_state {
+                    let amount = amount.min(state.initial_pledge);
+                    state.initial_pledge -= amount;
+                    T::Currency::unreserve(miner, amount);
+                }
+            });
+        }
+
+        /// Slashes up to `amount` of the miner's pledge.
+        /// Returns the amount that could not be covered by the pledge
+        fn slash_pledge(miner: &MinerAccountId<T>, amount: BalanceOf<T>) -> BalanceOf<T> {
+            let (imbalance, not_covered) = Self::take_pledge(miner, amount);
+            T::Slashed::on_unbalanced(imbalance);
+            not_covered
+        }
+
+        /// Takes up to `amount` of the miner's pledge out of its reserved balance.
+        /// Returns the funds taken and the amount that could not be covered by the pledge
+        fn take_pledge(
+            miner: &MinerAccountId<T>,
+            amount: BalanceOf<T>,
+        ) -> (NegativeImbalanceOf<T>, BalanceOf<T>) {
+            MinerStates::<T>::mutate(miner, |maybe_state| match maybe_state {
+                Some(state) => {
+                    let slashed = amount.min(state.initial_pledge);
+                    state.initial_pledge -= slashed;
+                    let (imbalance, not_slashed) = T::Currency::slash_reserved(miner, slashed);
+                    (imbalance, amount - slashed + not_slashed)
+                }
+                None => (NegativeImbalanceOf::<T>::zero(), amount),
+            })
+        }
+
+        /// Rewards of the miner that vested by `now` and are not unlocked yet
+        fn vested_funds(miner: &MinerAccountId<T>, now: BlockNumberFor<T>) -> BalanceOf<T> {
+            VestingFunds::<T>::get(miner)
+                .iter()
+                .take_while(|fund| fund.epoch <= now)
+                .fold(BalanceOf::<T>::zero(), |total, fund| {
+                    total.saturating_add(fund.amount)
+                })
+        }
+
+        /// Unlocks the rewards of the miner that vested by `now`, writes nothing if none did
+        fn unlock_vested_funds(miner: &MinerAccountId<T>, now: BlockNumberFor<T>) {
+            // following UnlockVestedFunds in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/miner/vesting_state.go
+            let mut funds = VestingFunds::<T>::get(miner);
+            let vested_count = funds.iter().take_while(|fund| fund.epoch <= now).count();
+            if vested_count == 0 {
+                return;
+            }
+            let vested = funds
+                .drain(..vested_count)
+                .fold(BalanceOf::<T>::zero(), |total, fund| {
+                    total.saturating_add(fund.amount)
+                });
+            if funds.is_empty() {
+                VestingFunds::<T>::remove(miner);
+            } else {
+                VestingFunds::<T>::insert(miner, funds);
+            }
+            MinerStates::<T>::mutate(miner, |maybe_state| {
+                if let Some(state) = maybe_state {
+                    let vested = vested.min(state.locked_funds);
+                    state.locked_funds -= vested;
+                    T::Currency::unreserve(miner, vested);
+                }
+            });
+        }
+
+        /// Balance of the miner that is not pledged, deposited, locked or owed
+        fn available_balance(
+            miner: &MinerAccountId<T>,
+            state: &MinerState<BlockNumberFor<T>, BalanceOf<T>>,
+        ) -> BalanceOf<T> {
+            // following GetAvailableBalance in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/miner/miner_state.go
+            T::Currency::total_balance(miner)
+                .saturating_sub(state.initial_pledge)
+                .saturating_sub(state.pre_commit_deposits)
+                .saturating_sub(state.locked_funds)
+                .saturating_sub(state.fee_debt)
+                .min(T::Currency::free_balance(miner))
+        }
+
+        /// Slashes `amount` from the miner's free balance, recording what it cannot cover as fee
+        /// debt
+        fn slash_free_balance(miner: &MinerAccountId<T>, amount: BalanceOf<T>) {
+            T::Slashed::on_unbalanced(Self::take_free_balance(miner, amount));
+        }
+
+        /// Takes `amount` out of the miner's free balance, recording what it cannot cover as fee
+        /// debt.
+        /// Returns the funds taken
+        fn take_free_balance(
+            miner: &MinerAccountId<T>,
+            amount: BalanceOf<T>,
+        ) -> NegativeImbalanceOf<T> {
+            // `Currency::slash` falls back to the reserved balance, which backs the pledge and
+            // deposits, so only the free balance is slashed
+            let slashable = amount.min(T::Currency::free_balance(miner));
+            let (imbalance, not_slashed) = T::Currency::slash(miner, slashable);
+
+            // following the fee debt of miner state, the part of the penalty the miner cannot
+            // pay is owed until repaid
+            let debt = amount - slashable + not_slashed;
+            if !debt.is_zero() {
+                MinerStates::<T>::mutate(miner, |maybe_state| {
+                    if let Some(state) = maybe_state {
+                        state.fee_debt = state.fee_debt.saturating_add(debt);
+                    }
+                });
+                Self::deposit_event(Event::DebtIncurred(miner.clone(), debt));
+            }
+            imbalance
+        }
+
+        /// Charges a fee to the miner from its pledge, then from its free balance
+        fn charge_fee(miner: &MinerAccountId<T>, fee: BalanceOf<T>) {
+            let not_covered = Self::slash_pledge(miner, fee);
+            Self::slash_free_balance(miner, not_covered);
+        }
+
+        /// Early termination penalty of a sector, half of its expected block reward for every
+        /// block it has been active, up to `TerminationLifetimeCap` blocks
+        fn termination_penalty(
+            sector: &SectorOnChainInfo<BlockNumberFor<T>, BalanceOf<T>>,
+            now: BlockNumberFor<T>,
+        ) -> BalanceOf<T> {
+            // following pledgePenaltyForTermination in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/miner/monies.go
+            let age = now
+                .saturating_sub(sector.activation)
+                .min(T::TerminationLifetimeCap::get());
+            sector
+                .expected_block_reward
+                .saturating_mul(age.saturated_into::<BalanceOf<T>>())
+                / BalanceOf::<T>::from(2u32)
+        }
+
+        /// Removes `sector_numbers` from `partition` and the miner's sectors and charges their
+        /// early termination penalty from their pledge, the rest of which is released. The deals
+        /// of the sectors are slashed by the market.
+        /// Returns the terminated sectors and their early termination penalty
+        fn terminate_partition_sectors(
+            miner: &MinerAccountId<T>,
+            partition: &mut Partition<BlockNumberFor<T>>,
+            sector_numbers: &[SectorNumber],
+            now: BlockNumberFor<T>,
+        ) -> (Vec<SectorNumber>, BalanceOf<T>) {
+            let sectors = Self::remove_partition_sectors(miner, partition, sector_numbers);
+            let deal_ids: Vec<DealId> = sectors
+                .iter()
+                .flat_map(|sector| sector.deal_ids.iter().copied())
+                .collect();
+            if !deal_ids.is_empty() {
+                T::Market::on_sectors_terminated(miner, &deal_ids, now);
+            }
+            let (penalty, pledge) = sectors.iter().fold(
+                (BalanceOf::<T>::zero(), BalanceOf::<T>::zero()),
+                |(penalty, pledge), sector| {
+                    (
+                        penalty.saturating_add(Self::termination_penalty(sector, now)),
+                        pledge.saturating_add(sector.initial_pledge),
+                    )
+                },
+            );
+            // following processEarlyTerminations, any part of the penalty not covered by the
+            // pledge of the terminated sectors is slashed from the free balance
+            let from_pledge = penalty.min(pledge);
+            Self::release_pledge(miner, pledge - from_pledge);
+            let not_covered = Self::slash_pledge(miner, from_pledge);
+            Self::slash_free_balance(miner, penalty - from_pledge + not_covered);
+            (
+                sectors
+                    .into_iter()
+                    .map(|sector| sector.sector_number)
+                    .collect(),
+                penalty,
+            )
+        }
+
+        /// Removes `sector_numbers` from `partition` and the miner's sectors, removing the power
+        /// of the active ones.
+        /// Returns the removed sectors
+        fn remove_partition_sectors(
+            miner: &MinerAccountId<T>,
+            partition: &mut Partition<BlockNumberFor<T>>,
+            sector_numbers: &[SectorNumber],
+        ) -> Vec<SectorOnChainInfo<BlockNumberFor<T>, BalanceOf<T>>> {
+            let sector_numbers: BTreeSet<SectorNumber> = sector_numbers
+                .iter()
+                .filter(|sector_number| partition.sectors.contains(sector_number))
+                .copied()
+                .collect();
+            // Faulty sectors have already lost their power
+            let active_sectors: Vec<SectorNumber> = sector_numbers
+                .iter()
+                .filter(|sector_number| !partition.faults.contains_key(sector_number))
+                .copied()
+                .collect();
+            Self::remove_sectors_power(miner, &active_sectors);
+
+            sector_numbers
+                .iter()
+                .filter_map(|sector_number| {
+                    partition.sectors.remove(sector_number);
+                    partition.faults.remove(sector_number);
+                    partition.recoveries.remove(sector_number);
+                    Sectors::<T>::take(miner, sector_number)
+                })
+                .collect()
+        }
+
+        /// Total raw bytes and quality adjusted power of the given sectors
+        fn sectors_power(
+            miner: &MinerAccountId<T>,
+            sector_numbers: &[SectorNumber],
+        ) -> (StoragePower<T>, StoragePower<T>) {
+            sector_numbers
+                .iter()
+                .filter_map(|sector_number| Sectors::<T>::get(miner, sector_number))
+                .map(|sector| {
+                    Self::sector_power(
+                        &sector.seal_proof,
+                        sector.expiration.saturating_sub(sector.activation),
+                        sector.deal_weight,
+                        sector.verified_deal_weight,
+                    )
+                })
+                .fold(
+                    (StoragePower::<T>::zero(), StoragePower::<T>::zero()),
+                    |(raw_total, qa_total), (raw, qa)| {
+                        (raw_total.saturating_add(raw), qa_total.saturating_add(qa))
+                    },
+                )
+        }
+
+        /// Adds the power of the given sectors to the miner's claim
+        fn add_sectors_power(miner: &MinerAccountId<T>, sector_numbers: &[SectorNumber]) {
+            let (raw_bytes_power, quality_adjusted_power) =
+                Self::sectors_power(miner, sector_numbers);
+            // The claim can only overflow if the total power of the network does, the sectors
+            // stay unpowered in that case
+            let _ = T::Power::update_claim(
+                miner.clone(),
+                PowerDelta::Increase(raw_bytes_power),
+                PowerDelta::Increase(quality_adjusted_power),
+            );
+        }
+
+        /// Removes the power of the given sectors from the miner's claim
+        fn remove_sectors_power(miner: &MinerAccountId<T>, sector_numbers: &[SectorNumber]) {
+            let (raw_bytes_power, quality_adjusted_power) =
+                Self::sectors_power(miner, sector_numbers);
+            // The claim cannot underflow as it includes the power of every active sector
+            let _ = T::Power::update_claim(
+                miner.clone(),
+                PowerDelta::Decrease(raw_bytes_power),
+                PowerDelta::Decrease(quality_adjusted_power),
+            );
+        }
+
+        /// Checks the signer can manage the miner's sectors and loads the deadlines of the
+        /// declared sectors, ensuring every sector belongs to its declared partition and that the
+        /// deadline is not currently being challenged.
+        /// Returns the loaded deadlines and all declared sector numbers
+        fn load_declared_sectors(
+            miner: &MinerAccountId<T>,
+            signer: &T::AccountId,
+            declarations: &[FaultDeclaration],
+        ) -> Result<
+            (
+                BTreeMap<DeadlineIndex, Deadline<BlockNumberFor<T>>>,
+                Vec<SectorNumber>,
+            ),
+            Error<T>,
+        > {
+            // following DeclarationsMax and AddressedSectorsMax, the work of a call is bounded
+            ensure!(
+                declarations.len() <= T::MaxDeclarations::get() as usize,
+                Error::<T>::TooManyDeclarations
+            );
+            ensure!(
+                declared_sectors_count(declarations) <= u64::from(T::MaxDeclaredSectors::get()),
+                Error::<T>::TooManyDeclaredSectors
+            );
+            let miner_info = Miners::<T>::try_get(miner).map_err(|_| Error::<T>::NoSuchMiner)?;
+            ensure!(
+                miner_info.is_controlling_address(signer),
+                Error::<T>::InvalidSigner
+            );
+            let current_deadline = Self::current_deadline(miner)
+                .ok_or(Error::<T>::NoSuchMiner)?
+                .index;
+
+            let mut deadlines = BTreeMap::new();
+            let mut declared_sectors = Vec::new();
+            for declaration in declarations.iter() {
+                ensure!(
+                    declaration.deadline < T::WPoStPeriodDeadlines::get(),
+                    Error::<T>::InvalidDeadline
+                );
+                // following FaultDeclarationCutoff, the open deadline has already been challenged
+                // and its partitions cannot change
+                ensure!(
+                    declaration.deadline != current_deadline,
+                    Error::<T>::DeadlineImmutable
+                );
+                let deadline_state = deadlines
+                    .entry(declaration.deadline)
+                    .or_insert_with(|| Deadlines::<T>::get(miner, declaration.deadline));
+                let partition = deadline_state
+                    .partitions
+                    .get(declaration.partition as usize)
+                    .ok_or(Error::<T>::NoSuchPartition)?;
+                for sector_number in declaration.sectors.iter() {
+                    ensure!(
+                        partition.sectors.contains(sector_number),
+                        Error::<T>::NoSuchSector
+                    );
+                    declared_sectors.push(*sector_number);
+                }
+            }
+            Ok((deadlines, declared_sectors))
+        }
+
+        /// Removes a pre-commit that was not proven in time and forfeits its deposit
+        fn process_pre_commit_expiration(
+            miner: &MinerAccountId<T>,
+            sector_number: SectorNumber,
+            now: BlockNumberFor<T>,
+        ) {
+            // following checkPrecommitExpiry in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/miner/miner_actor.go
+            let pre_commit = match PreCommittedSectors::<T>::get(miner, sector_number) {
+                Some(pre_commit) => pre_commit,
+                None => return,
+            };
+            // Stale entry, the sector was proven and pre-committed again since
+            if pre_commit.pre_commit_epoch + T::MaxProveCommitDuration::get() >= now {
+                return;
+            }
+            PreCommittedSectors::<T>::remove(miner, sector_number);
+            let deposit = MinerStates::<T>::mutate(miner, |maybe_state| match maybe_state {
+                Some(state) => {
+                    let deposit = pre_commit.pre_commit_deposit.min(state.pre_commit_deposits);
+                    state.pre_commit_deposits -= deposit;
+                    let (imbalance, _) = T::Currency::slash_reserved(miner, deposit);
+                    T::Slashed::on_unbalanced(imbalance);
+                    deposit
+                }
+                None => Zero::zero(),
+            });
+            Self::deposit_event(Event::PreCommitExpired(
+                miner.clone(),
+                sector_number,
+                deposit,
+            ));
+        }
+
+        /// Removes a sector and its power when it reaches its expiration
+        fn process_sector_expiration(
+            miner: &MinerAccountId<T>,
+            sector_number: SectorNumber,
+            now: BlockNumberFor<T>,
+        ) {
+            // following the expiration queue of partitions in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/miner/partition_state.go
+            let sector = match Sectors::<T>::get(miner, sector_number) {
+                Some(sector) => sector,
+                None => return,
+            };
+            // Stale entry, the sector expiration was extended
+            if sector.expiration != now {
+                return;
+            }
+            Deadlines::<T>::mutate(miner, sector.deadline, |deadline_state| {
+                if let Some(partition) =
+                    deadline_state.partitions.get_mut(sector.partition as usize)
+                {
+                    Self::remove_partition_sectors(miner, partition, &[sector_number]);
+                }
+            });
+            Self::release_pledge(miner, sector.initial_pledge);
+            Self::deposit_event(Event::SectorExpired(miner.clone(), sector_number));
+        }
+
+        /// Closes the current deadline of the miner and schedules the next one.
+        /// Returns the weight consumed, proportional to the sectors of the deadline
+        fn process_deadline_end(miner: &MinerAccountId<T>, now: BlockNumberFor<T>) -> Weight {
+            // following handleProvingDeadline in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/miner/miner_actor.go
+            let deadline = match MinerStates::<T>::get(miner) {
+                Some(state) => Self::deadline_info(&state),
+                None => return T::DbWeight::get().reads(1),
+            };
+            // Stale entry, the deadline was already processed
+            if deadline.close != now {
+                return T::DbWeight::get().reads(1);
+            }
+            Self::unlock_vested_funds(miner, now);
+
+            // following processMissingPoStFaults, every active sector in a partition without a
+            // proof becomes faulty and its power is removed
+            let mut faulted_sectors = Vec::new();
+            let mut sector_count: Weight = 0;
+            Deadlines::<T>::mutate(miner, deadline.index, |deadline_state| {
+                sector_count = deadline_state
+                    .partitions
+                    .iter()
+                    .map(|partition| partition.sectors.len() as Weight)
+                    .sum();
+                for (partition_index, partition) in deadline_state.partitions.iter_mut().enumerate()
+                {
+                    if deadline_state
+                        .post_submissions
+                        .contains(&(partition_index as PartitionIndex))
+                    {
+                        continue;
+                    }
+                    // Recoveries are retracted as the sectors were not proven
+                    partition.recoveries.clear();
+                    let new_faults: Vec<SectorNumber> =
+                        partition.active_sectors().copied().collect();
+                    for sector_number in new_faults.iter() {
+                        partition.faults.insert(*sector_number, now);
+                    }
+                    faulted_sectors.extend(new_faults);
+                }
+                deadline_state.post_submissions.clear();
+            });
+            if !faulted_sectors.is_empty() {
+                Self::remove_sectors_power(miner, &faulted_sectors);
+                Self::deposit_event(Event::SectorsFaulted(miner.clone(), faulted_sectors));
+            }
+
+            // following PledgePenaltyForContinuedFault, every faulty sector of the deadline is
+            // charged a fee
+            let fault_fee = Deadlines::<T>::get(miner, deadline.index)
+                .partitions
+                .iter()
+                .flat_map(|partition| partition.faults.keys())
+                .filter_map(|sector_number| Sectors::<T>::get(miner, sector_number))
+                .fold(BalanceOf::<T>::zero(), |fee, sector| {
+                    fee.saturating_add(Self::fault_fee(&sector))
+                });
+            if !fault_fee.is_zero() {
+                Self::charge_fee(miner, fault_fee);
+                Self::deposit_event(Event::FaultFeeCharged(miner.clone(), fault_fee));
+            }
+
+            // following the fault expiration queue, sectors faulty for longer than
+            // `MaxFaultDuration` are terminated
+            let mut terminated_sectors = Vec::new();
+            let mut penalty = BalanceOf::<T>::zero();
+            Deadlines::<T>::mutate(miner, deadline.index, |deadline_state| {
+                for partition in deadline_state.partitions.iter_mut() {
+                    let expired_faults: Vec<SectorNumber> = partition
+                        .faults
+                        .iter()
+                        .filter(|(_, faulted_at)| {
+                            now.saturating_sub(**faulted_at) >= T::MaxFaultDuration::get()
+                        })
+                        .map(|(sector_number, _)| *sector_number)
+                        .collect();
+                    let (sectors, sectors_penalty) =
+                        Self::terminate_partition_sectors(miner, partition, &expired_faults, now);
+                    terminated_sectors.extend(sectors);
+                    penalty = penalty.saturating_add(sectors_penalty);
+                }
+            });
+            if !terminated_sectors.is_empty() {
+                Self::deposit_event(Event::SectorsTerminated(
+                    miner.clone(),
+                    terminated_sectors,
+                    penalty,
+                ));
+            }
+
+            // The state is reloaded as fees and penalties above update the miner's pledge
+            let next_deadline = MinerStates::<T>::mutate(miner, |maybe_state| {
+                maybe_state.as_mut().map(|state| {
+                    state.current_deadline += 1;
+                    if state.current_deadline == T::WPoStPeriodDeadlines::get() {
+                        state.current_deadline = 0;
+                        state.proving_period_start += T::WPoStProvingPeriod::get();
+                    }
+                    Self::deadline_info(state)
+                })
+            });
+            if let Some(next_deadline) = next_deadline {
+                DeadlineCron::<T>::append(next_deadline.close, miner);
+            }
+            // Every sector can be read for its power, fee and termination, and removed
+            T::DbWeight::get().reads_writes(8 + 3 * sector_count, 8 + 2 * sector_count)
+        }
+    }
+
+    impl<T: Config> RewardDistributor for Pallet<T> {
+        type AccountId = MinerAccountId<T>;
+        type Balance = BalanceOf<T>;
+
+        fn distribute_reward(miner: &MinerAccountId<T>, reward: BalanceOf<T>) {
+            // following ApplyRewards in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/miner/miner_actor.go
+            // Note: The whole reward vests, the reward of an account that is not a miner is
+            // left liquid
+            let _ = Self::add_locked_funds(miner, reward);
+        }
+    }
+
+    impl<T: Config> MinerAccounts for Pallet<T> {
+        type AccountId = T::AccountId;
+
+        fn owner(miner: &T::AccountId) -> Option<T::AccountId> {
+            Miners::<T>::get(miner).map(|miner_info| miner_info.owner)
+        }
+
+        fn worker(miner: &T::AccountId) -> Option<T::AccountId> {
+            Miners::<T>::get(miner).map(|miner_info| miner_info.worker)
+        }
+
+        fn is_controlling_address(miner: &T::AccountId, address: &T::AccountId) -> bool {
+            Miners::<T>::get(miner).map_or(false, |miner_info| {
+                miner_info.is_controlling_address(address)
+            })
+        }
+    }
+
+    impl<T: Config> WinningPoStSectors for Pallet<T> {
+        type AccountId = T::AccountId;
+
+        fn challenged_sectors(miner: &T::AccountId, randomness: &[u8]) -> Vec<SectorInfo> {
+            // Note: A single sector is challenged, drawn from the active sectors of the miner in
+            // sector number order by the hash of the randomness
+            let mut active_sectors: Vec<SectorNumber> = (0..T::WPoStPeriodDeadlines::get())
+                .flat_map(|index| Deadlines::<T>::get(miner, index).partitions)
+                .flat_map(|partition| partition.active_sectors().copied().collect::<Vec<_>>())
+                .collect();
+            if active_sectors.is_empty() {
+                return Vec::new();
+            }
+            active_sectors.sort_unstable();
+            let draw = BlakeTwo256::hash(randomness).to_low_u64_le() % active_sectors.len() as u64;
+            Sectors::<T>::get(miner, active_sectors[draw as usize])
+                .map(|sector| SectorInfo {
+                    seal_proof: sector.seal_proof,
+                    sector_number: sector.sector_number,
+                    sealed_cid: sector.sealed_cid,
+                })
+                .into_iter()
+                .collect()
+        }
     }
 }
 
@@ -305,10 +2049,32 @@ pub struct MinerInfo<
     controllers: Vec<AccountId>,
     /// Miner's libp2p PeerId
     peer_id: PeerId,
+    /// Proof type the sectors of this Miner are sealed with
+    seal_proof_type: RegisteredSealProof,
     /// Update to this worker address to at defined time
     pending_worker: Option<WorkerKeyChange<AccountId, BlockNumber>>,
     /// Update to this owner address when it confirms
     pending_owner: Option<AccountId>,
+    /// Block at which the exclusion of the miner from block production after its last consensus
+    /// fault ends
+    consensus_fault_elapsed: Option<BlockNumber>,
+}
+
+impl<AccountId, BlockNumber, PeerId> MinerInfo<AccountId, BlockNumber, PeerId>
+where
+    AccountId: Encode + Decode + Eq + PartialEq,
+    BlockNumber: Encode + Decode + Eq + PartialEq,
+    PeerId: Encode + Decode + Eq + PartialEq,
+{
+    /// Accounts allowed to submit sector proofs on behalf of the miner
+    fn is_worker_or_controller(&self, account: &AccountId) -> bool {
+        &self.worker == account || self.controllers.iter().any(|c| c == account)
+    }
+
+    /// Accounts allowed to manage the miner's sectors, the owner, worker and controllers
+    fn is_controlling_address(&self, account: &AccountId) -> bool {
+        &self.owner == account || self.is_worker_or_controller(account)
+    }
 }
 
 #[derive(Encode, Decode, Debug)]
@@ -329,3 +2095,85 @@ pub enum MinerControllers<AccountId> {
     /// Miner controller accounts should not change
     NoChange,
 }
+
+/// Information submitted by a miner to pre-commit a sector
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+pub struct SectorPreCommitInfo<BlockNumber> {
+    /// Proof type the sector is sealed with
+    pub seal_proof: RegisteredSealProof,
+    /// Sector number, unique per miner
+    pub sector_number: SectorNumber,
+    /// CommR of the sealed sector
+    pub sealed_cid: Vec<u8>,
+    /// Block from which the seal randomness is drawn
+    pub seal_rand_epoch: BlockNumber,
+    /// Deals to be activated with this sector
+    pub deal_ids: Vec<DealId>,
+    /// Block at which the sector expires
+    pub expiration: BlockNumber,
+}
+
+/// Pre-committed sector as stored on chain, waiting for its seal proof
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+pub struct SectorPreCommitOnChainInfo<BlockNumber, Balance> {
+    /// Information submitted with the pre-commit
+    pub info: SectorPreCommitInfo<BlockNumber>,
+    /// Block at which the sector was pre-committed
+    pub pre_commit_epoch: BlockNumber,
+    /// Deposit reserved until the sector is proven
+    pub pre_commit_deposit: Balance,
+}
+
+/// Proven sector as stored on chain
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+pub struct SectorOnChainInfo<BlockNumber, Balance> {
+    /// Sector number, unique per miner
+    pub sector_number: SectorNumber,
+    /// Proof type the sector is sealed with
+    pub seal_proof: RegisteredSealProof,
+    /// CommR of the sealed sector
+    pub sealed_cid: Vec<u8>,
+    /// Deals activated with this sector
+    pub deal_ids: Vec<DealId>,
+    /// Block at which the sector was proven
+    pub activation: BlockNumber,
+    /// Block at which the sector expires
+    pub expiration: BlockNumber,
+    /// Space time of the deals activated with this sector
+    pub deal_weight: DealWeight,
+    /// Space time of the verified deals activated with this sector
+    pub verified_deal_weight: DealWeight,
+    /// Deadline the sector is assigned to
+    pub deadline: DeadlineIndex,
+    /// Partition within the deadline the sector is assigned to
+    pub partition: PartitionIndex,
+    /// Share of the block reward the sector was expected to earn at activation
+    pub expected_block_reward: Balance,
+    /// Collateral reserved when the sector was proven
+    pub initial_pledge: Balance,
+}
+
+/// Proving state of a miner
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+pub struct MinerState<BlockNumber, Balance> {
+    /// First block of the current proving period
+    pub proving_period_start: BlockNumber,
+    /// Index of the deadline currently being proven
+    pub current_deadline: DeadlineIndex,
+    /// Pledge collateral reserved for the miner's sectors
+    pub initial_pledge: Balance,
+    /// Deposits reserved for the miner's pre-commits
+    pub pre_commit_deposits: Balance,
+    /// Penalties the miner owes and could not pay
+    pub fee_debt: Balance,
+    /// Rewards reserved until they vest
+    pub locked_funds: Balance,
+}
+
+/// Locked rewards of a miner vesting at a block
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+pub struct VestingFund<BlockNumber, Balance> {
+    /// Block the funds vest at
+    pub epoch: BlockNumber,
+    pub amount: Balance,
+}
@@ -1,11 +1,23 @@
 use crate as pallet_miner;
-use frame_support::parameter_types;
+use codec::{Decode, Encode};
+use frame_support::{
+    parameter_types,
+    traits::{Currency, OnUnbalanced},
+};
 use frame_system as system;
+use pallet_common::{
+    AccountIdConversion, ConsensusFault, ConsensusFaultType, ConsensusFaultVerifier,
+    ConsensusFaultVerifyInfo, DealId, DealWeight, FakeVerifier, Market, MinerId,
+    RegisteredSealProof,
+};
 use sp_core::H256;
 use sp_runtime::{
     testing::Header,
-    traits::{BlakeTwo256, IdentityLookup},
+    traits::{BlakeTwo256, Convert, IdentityLookup},
+    DispatchError,
 };
+use std::cell::RefCell;
+use std::collections::BTreeMap;
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
 type Block = frame_system::mocking::MockBlock<Test>;
@@ -17,7 +29,8 @@ frame_support::construct_runtime!(
         UncheckedExtrinsic = UncheckedExtrinsic,
         {
             System: frame_system::{Module, Call, Config, Storage, Event<T>},
-            Power: pallet_power::{Module, Storage},
+            Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
+            Power: pallet_power::{Module, Storage, Event<T>},
             Miner: pallet_miner::{Module, Call, Storage, Event<T>},
         }
 );
@@ -46,32 +59,252 @@ impl system::Config for Test {
     type BlockHashCount = BlockHashCount;
     type Version = ();
     type PalletInfo = PalletInfo;
-    type AccountData = ();
+    type AccountData = pallet_balances::AccountData<u64>;
     type OnNewAccount = ();
     type OnKilledAccount = ();
     type SystemWeightInfo = ();
     type SS58Prefix = SS58Prefix;
 }
 
+parameter_types! {
+    pub const ExistentialDeposit: u64 = 1;
+    pub const MaxLocks: u32 = 50;
+}
+
+impl pallet_balances::Config for Test {
+    type MaxLocks = MaxLocks;
+    type Balance = u64;
+    type Event = Event;
+    type DustRemoval = ();
+    type ExistentialDeposit = ExistentialDeposit;
+    type AccountStore = System;
+    type WeightInfo = ();
+}
+
+pub const TREASURY: u64 = 999;
+
+/// Deposits the funds slashed from miners in `TREASURY`
+pub struct MockTreasury;
+
+impl OnUnbalanced<pallet_balances::NegativeImbalance<Test>> for MockTreasury {
+    fn on_nonzero_unbalanced(amount: pallet_balances::NegativeImbalance<Test>) {
+        Balances::resolve_creating(&TREASURY, amount);
+    }
+}
+
+/// Consensus minimum power of a single sector
+pub struct SectorSize;
+
+impl Convert<RegisteredSealProof, u128> for SectorSize {
+    fn convert(seal_proof: RegisteredSealProof) -> u128 {
+        seal_proof.sector_size() as u128
+    }
+}
+
+parameter_types! {
+    pub ConsensusMinerMinMiners: u64 = 3;
+    pub const PowerHistoryRetention: u64 = 30;
+}
+
 impl pallet_power::Config for Test {
+    type Event = Event;
     type PeerId = Vec<u8>;
     type StoragePower = u128;
+    type ConsensusMinerMinPower = SectorSize;
+    type ConsensusMinerMinMiners = ConsensusMinerMinMiners;
+    type PowerHistoryRetention = PowerHistoryRetention;
 }
 
 parameter_types! {
     pub BlockDelay: u64 = 5;
+    pub MaxPreCommitRandomnessLookback: u64 = 100;
+    pub PreCommitChallengeDelay: u64 = 10;
+    pub MaxProveCommitDuration: u64 = 50;
+    pub WPoStProvingPeriod: u64 = 60;
+    pub WPoStPeriodDeadlines: u64 = 6;
+    pub WPoStPartitionSectors: u64 = 2;
+    pub WPoStChallengeLookback: u64 = 5;
+    pub EpochReward: u64 = 1_000;
+    pub TerminationLifetimeCap: u64 = 20;
+    pub MaxFaultDuration: u64 = 60;
+    pub MaxSectorLifetime: u64 = 2_000;
+    pub MaxDeclarations: u32 = 3;
+    pub MaxDeclaredSectors: u32 = 5;
+    pub InitialPledgeProjectionPeriod: u64 = 10;
+    pub FaultFeeProjectionPeriod: u64 = 2;
+    pub PreCommitDepositProjectionPeriod: u64 = 5;
+    pub RewardVestingPeriod: u64 = 40;
+    pub RewardVestingSteps: u32 = 4;
+    pub QualityBaseMultiplier: u64 = 10;
+    pub DealWeightMultiplier: u64 = 20;
+    pub VerifiedDealWeightMultiplier: u64 = 100;
+    pub ConsensusFaultFactor: u32 = 5;
+    pub ConsensusFaultReporterDivisor: u32 = 4;
+    pub ConsensusFaultIneligibilityDuration: u64 = 30;
+    pub ConsensusFaultMaxAge: u64 = 20;
+}
+
+/// Block header understood by `MockConsensusFaultVerifier`, the worker stands in for the
+/// signature of the header
+pub fn block_header(worker: u64, epoch: u64, parent: u64) -> Vec<u8> {
+    (worker, epoch, parent).encode()
+}
+
+/// Proves double fork mining with two distinct headers of the same epoch signed by the worker
+pub struct MockConsensusFaultVerifier;
+
+impl ConsensusFaultVerifier<u64> for MockConsensusFaultVerifier {
+    fn verify_consensus_fault(info: &ConsensusFaultVerifyInfo) -> Option<ConsensusFault<u64>> {
+        let (worker1, epoch1, _) = <(u64, u64, u64)>::decode(&mut &info.header1[..]).ok()?;
+        let (worker2, epoch2, _) = <(u64, u64, u64)>::decode(&mut &info.header2[..]).ok()?;
+        if info.header1 == info.header2
+            || worker1.encode() != info.worker
+            || worker2.encode() != info.worker
+            || epoch1 != epoch2
+        {
+            return None;
+        }
+        Some(ConsensusFault {
+            epoch: epoch1,
+            fault_type: ConsensusFaultType::DoubleForkMining,
+        })
+    }
+}
+
+thread_local! {
+    static DEAL_WEIGHTS: RefCell<BTreeMap<DealId, (DealWeight, bool)>> =
+        RefCell::new(BTreeMap::new());
+    static ACTIVATED_DEALS: RefCell<Vec<(DealId, u64)>> = RefCell::new(Vec::new());
+    static TERMINATED_DEALS: RefCell<Vec<(DealId, u64)>> = RefCell::new(Vec::new());
+    static ESCROW_HOLDERS: RefCell<Vec<u64>> = RefCell::new(Vec::new());
+}
+
+/// Publishes a deal of `weight` in `MockMarket`, counted as verified deal weight if `verified`
+pub fn set_deal_weight(deal_id: DealId, weight: DealWeight, verified: bool) {
+    DEAL_WEIGHTS.with(|deals| deals.borrow_mut().insert(deal_id, (weight, verified)));
+}
+
+/// Deals activated in `MockMarket` with the block they were activated at
+pub fn activated_deals() -> Vec<(DealId, u64)> {
+    ACTIVATED_DEALS.with(|deals| deals.borrow().clone())
+}
+
+/// Deals terminated in `MockMarket` with the block they were terminated at
+pub fn terminated_deals() -> Vec<(DealId, u64)> {
+    TERMINATED_DEALS.with(|deals| deals.borrow().clone())
+}
+
+/// Gives `address` funds in escrow in `MockMarket`
+pub fn add_escrow(address: u64) {
+    ESCROW_HOLDERS.with(|holders| holders.borrow_mut().push(address));
+}
+
+/// Accepts the deals published with `set_deal_weight` that are not activated yet
+pub struct MockMarket;
+
+impl Market for MockMarket {
+    type AccountId = u64;
+    type BlockNumber = u64;
+
+    fn verify_deals_for_activation(
+        _miner: &u64,
+        deal_ids: &[DealId],
+        _sector_activation: u64,
+        _sector_expiration: u64,
+        _sector_size: u64,
+    ) -> Result<(DealWeight, DealWeight), DispatchError> {
+        let activated = activated_deals();
+        deal_ids
+            .iter()
+            .try_fold((0, 0), |(total, verified_total), deal_id| {
+                if activated
+                    .iter()
+                    .any(|(activated_id, _)| activated_id == deal_id)
+                {
+                    return Err(DispatchError::Other("DealAlreadyActivated"));
+                }
+                DEAL_WEIGHTS
+                    .with(|deals| deals.borrow().get(deal_id).copied())
+                    .map(|(weight, verified)| {
+                        if verified {
+                            (total, verified_total + weight)
+                        } else {
+                            (total + weight, verified_total)
+                        }
+                    })
+                    .ok_or(DispatchError::Other("NoSuchDeal"))
+            })
+    }
+
+    fn activate_deals(deal_ids: &[DealId], sector_activation: u64) {
+        ACTIVATED_DEALS.with(|deals| {
+            deals
+                .borrow_mut()
+                .extend(deal_ids.iter().map(|deal_id| (*deal_id, sector_activation)))
+        });
+    }
+
+    fn on_sectors_terminated(_miner: &u64, deal_ids: &[DealId], epoch: u64) {
+        TERMINATED_DEALS.with(|deals| {
+            deals
+                .borrow_mut()
+                .extend(deal_ids.iter().map(|deal_id| (*deal_id, epoch)))
+        });
+    }
+
+    fn has_escrow(address: &u64) -> bool {
+        ESCROW_HOLDERS.with(|holders| holders.borrow().contains(address))
+    }
 }
 
 impl pallet_miner::Config for Test {
     type Event = Event;
     type Power = Power;
+    type Currency = Balances;
+    type Slashed = MockTreasury;
     type BlockDelay = BlockDelay;
+    type MaxPreCommitRandomnessLookback = MaxPreCommitRandomnessLookback;
+    type PreCommitChallengeDelay = PreCommitChallengeDelay;
+    type MaxProveCommitDuration = MaxProveCommitDuration;
+    type ProofVerifier = FakeVerifier;
+    type WPoStProvingPeriod = WPoStProvingPeriod;
+    type WPoStPeriodDeadlines = WPoStPeriodDeadlines;
+    type WPoStPartitionSectors = WPoStPartitionSectors;
+    type WPoStChallengeLookback = WPoStChallengeLookback;
+    type EpochReward = EpochReward;
+    type TerminationLifetimeCap = TerminationLifetimeCap;
+    type MaxFaultDuration = MaxFaultDuration;
+    type MaxSectorLifetime = MaxSectorLifetime;
+    type MaxDeclarations = MaxDeclarations;
+    type MaxDeclaredSectors = MaxDeclaredSectors;
+    type InitialPledgeProjectionPeriod = InitialPledgeProjectionPeriod;
+    type FaultFeeProjectionPeriod = FaultFeeProjectionPeriod;
+    type PreCommitDepositProjectionPeriod = PreCommitDepositProjectionPeriod;
+    type RewardVestingPeriod = RewardVestingPeriod;
+    type RewardVestingSteps = RewardVestingSteps;
+    type Market = MockMarket;
+    type QualityBaseMultiplier = QualityBaseMultiplier;
+    type DealWeightMultiplier = DealWeightMultiplier;
+    type VerifiedDealWeightMultiplier = VerifiedDealWeightMultiplier;
+    type ConsensusFaultVerifier = MockConsensusFaultVerifier;
+    type ConsensusFaultFactor = ConsensusFaultFactor;
+    type ConsensusFaultReporterDivisor = ConsensusFaultReporterDivisor;
+    type ConsensusFaultIneligibilityDuration = ConsensusFaultIneligibilityDuration;
+    type ConsensusFaultMaxAge = ConsensusFaultMaxAge;
 }
 
+/// Free balance of the first miner account at genesis
+pub const MINER_BALANCE: u64 = 1_000_000;
+
 // Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
-    system::GenesisConfig::default()
+    let mut t = system::GenesisConfig::default()
         .build_storage::<Test>()
-        .unwrap()
-        .into()
+        .unwrap();
+    pallet_balances::GenesisConfig::<Test> {
+        balances: vec![(MinerId(1).into_account(), MINER_BALANCE)],
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+    t.into()
 }
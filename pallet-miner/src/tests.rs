@@ -1,9 +1,25 @@
 // Imports created by construct_runtime macros are unresolved by rust analyzer
 use crate as pallet_miner;
-use crate::mock::{new_test_ext, Event, Miner, Origin, System, Test};
-use crate::{Error, MinerControllers};
-use frame_support::{assert_noop, assert_ok, dispatch::DispatchResultWithPostInfo};
-use pallet_common::{AccountIdConversion, MinerId};
+use crate::mock::{
+    activated_deals, add_escrow, block_header, new_test_ext, set_deal_weight, terminated_deals,
+    Balances, Event, Miner, Origin, Power, System, Test, MINER_BALANCE, TREASURY,
+};
+use crate::{
+    DeadlineIndex, Error, FaultDeclaration, MinerControllers, PartitionIndex, SectorPreCommitInfo,
+    VestingFund,
+};
+use codec::Encode;
+use frame_support::{
+    assert_noop, assert_ok,
+    dispatch::{DispatchError, DispatchResultWithPostInfo},
+    traits::{Currency, LockableCurrency, OnInitialize, WithdrawReasons},
+};
+use pallet_common::{
+    AccountIdConversion, Claim, FakeVerifier, MinerAccounts, MinerId, Power as PowerTrait,
+    RegisteredSealProof, RewardDistributor, SealVerifyInfo, SectorInfo, SectorNumber,
+    WindowPoStVerifyInfo, WinningPoStSectors,
+};
+use sp_std::collections::btree_set::BTreeSet;
 
 const WORKER: u64 = 33;
 const PEERID_BYTE: u8 = 9;
@@ -15,7 +31,163 @@ const FIRST_MINER_ADDR: u64 = 1590839634285;
 fn create_miner_for(
     owner: <Test as frame_system::Config>::AccountId,
 ) -> DispatchResultWithPostInfo {
-    Miner::create(Origin::signed(1), owner, WORKER, vec![PEERID_BYTE])
+    Miner::create(
+        Origin::signed(1),
+        owner,
+        WORKER,
+        RegisteredSealProof::StackedDrg2KiBV1,
+        vec![PEERID_BYTE],
+    )
+}
+
+fn pre_commit_info(
+    sector_number: SectorNumber,
+    seal_rand_epoch: <Test as frame_system::Config>::BlockNumber,
+) -> SectorPreCommitInfo<<Test as frame_system::Config>::BlockNumber> {
+    SectorPreCommitInfo {
+        seal_proof: RegisteredSealProof::StackedDrg2KiBV1,
+        sector_number,
+        sealed_cid: vec![sector_number as u8],
+        seal_rand_epoch,
+        deal_ids: Vec::new(),
+        expiration: 1_000,
+    }
+}
+
+fn run_to_block(n: u64) {
+    while System::block_number() < n {
+        let block = System::block_number() + 1;
+        System::set_block_number(block);
+        Miner::on_initialize(block);
+    }
+}
+
+// Runs blocks until `deadline` of the first miner is open
+fn run_to_deadline_open(deadline: DeadlineIndex) {
+    loop {
+        let deadline_info = Miner::current_deadline(&FIRST_MINER_ADDR).unwrap();
+        if deadline_info.index == deadline && deadline_info.is_open(System::block_number()) {
+            break;
+        }
+        run_to_block(System::block_number() + 1);
+    }
+}
+
+// Seal proof of the first miner's pre-committed `sector_number` accepted by `FakeVerifier`, empty
+// if there is no such pre-commit
+fn seal_proof(sector_number: SectorNumber) -> Vec<u8> {
+    let pre_commit = match Miner::pre_committed_sectors(FIRST_MINER_ADDR, sector_number) {
+        Some(pre_commit) => pre_commit,
+        None => return Vec::new(),
+    };
+    let interactive_epoch = pre_commit.pre_commit_epoch
+        + <Test as pallet_miner::Config>::PreCommitChallengeDelay::get();
+    FakeVerifier::seal_proof(&SealVerifyInfo {
+        seal_proof: pre_commit.info.seal_proof,
+        prover: FIRST_MINER_ADDR.encode(),
+        sector_number,
+        sealed_cid: pre_commit.info.sealed_cid,
+        deal_ids: pre_commit.info.deal_ids,
+        randomness: System::block_hash(pre_commit.info.seal_rand_epoch)
+            .as_ref()
+            .to_vec(),
+        interactive_randomness: System::block_hash(interactive_epoch).as_ref().to_vec(),
+        proof: Vec::new(),
+    })
+}
+
+// WindowPoSt of the first miner over `partitions` of `deadline` accepted by `FakeVerifier` in the
+// current deadline
+fn window_post_proof(deadline: DeadlineIndex, partitions: &[PartitionIndex]) -> Vec<u8> {
+    let deadline_state = Miner::deadlines(FIRST_MINER_ADDR, deadline);
+    let challenged_sectors = partitions
+        .iter()
+        .filter_map(|partition_index| deadline_state.partitions.get(*partition_index as usize))
+        .flat_map(|partition| partition.challenged_sectors())
+        .filter_map(|sector_number| Miner::sectors(FIRST_MINER_ADDR, sector_number))
+        .map(|sector| SectorInfo {
+            seal_proof: sector.seal_proof,
+            sector_number: sector.sector_number,
+            sealed_cid: sector.sealed_cid,
+        })
+        .collect();
+    let challenge = Miner::current_deadline(&FIRST_MINER_ADDR)
+        .unwrap()
+        .challenge;
+    FakeVerifier::window_post_proof(&WindowPoStVerifyInfo {
+        prover: FIRST_MINER_ADDR.encode(),
+        randomness: System::block_hash(challenge).as_ref().to_vec(),
+        challenged_sectors,
+        proof: Vec::new(),
+    })
+}
+
+// Creates a miner for `owner` and pre-commits `sector_number` at block 10
+fn create_miner_with_pre_commit(
+    owner: <Test as frame_system::Config>::AccountId,
+    sector_number: SectorNumber,
+) {
+    assert_ok!(create_miner_for(owner));
+    System::set_block_number(10);
+    assert_ok!(Miner::pre_commit_sector(
+        Origin::signed(WORKER),
+        FIRST_MINER_ADDR,
+        pre_commit_info(sector_number, 5)
+    ));
+}
+
+// Creates a miner for `owner` with `sector_number` proven at block 21
+fn create_miner_with_sector(
+    owner: <Test as frame_system::Config>::AccountId,
+    sector_number: SectorNumber,
+) {
+    create_miner_with_pre_commit(owner, sector_number);
+    run_to_block(21);
+    assert_ok!(Miner::prove_commit_sector(
+        Origin::signed(WORKER),
+        FIRST_MINER_ADDR,
+        sector_number,
+        seal_proof(sector_number)
+    ));
+}
+
+// Creates a miner for `owner` with a sector pre-committed at block 10 from `info` and proven at
+// block 21
+fn create_miner_with_sector_info(
+    owner: <Test as frame_system::Config>::AccountId,
+    info: SectorPreCommitInfo<<Test as frame_system::Config>::BlockNumber>,
+) {
+    assert_ok!(create_miner_for(owner));
+    System::set_block_number(10);
+    let sector_number = info.sector_number;
+    assert_ok!(Miner::pre_commit_sector(
+        Origin::signed(WORKER),
+        FIRST_MINER_ADDR,
+        info
+    ));
+    run_to_block(21);
+    assert_ok!(Miner::prove_commit_sector(
+        Origin::signed(WORKER),
+        FIRST_MINER_ADDR,
+        sector_number,
+        seal_proof(sector_number)
+    ));
+}
+
+// Pre-commits `sector_number` for the first miner and proves it after the challenge delay
+fn add_sector(sector_number: SectorNumber) {
+    assert_ok!(Miner::pre_commit_sector(
+        Origin::signed(WORKER),
+        FIRST_MINER_ADDR,
+        pre_commit_info(sector_number, System::block_number() - 1)
+    ));
+    run_to_block(System::block_number() + 11);
+    assert_ok!(Miner::prove_commit_sector(
+        Origin::signed(WORKER),
+        FIRST_MINER_ADDR,
+        sector_number,
+        seal_proof(sector_number)
+    ));
 }
 
 #[test]
@@ -43,6 +215,7 @@ fn create_miner() {
             Origin::signed(1),
             owner,
             worker,
+            RegisteredSealProof::StackedDrg2KiBV1,
             peer_id.clone()
         ));
 
@@ -55,6 +228,10 @@ fn create_miner() {
         assert_eq!(new_miner_info.owner, owner);
         assert_eq!(new_miner_info.worker, worker);
         assert_eq!(new_miner_info.peer_id, peer_id);
+        assert_eq!(
+            new_miner_info.seal_proof_type,
+            RegisteredSealProof::StackedDrg2KiBV1
+        );
         assert_eq!(new_miner_info.controllers.len(), 0);
         assert_eq!(System::event_count(), 1);
 
@@ -543,3 +720,2116 @@ fn change_peer_id_rejects_invalid_signer() {
         );
     });
 }
+
+#[test]
+fn pre_commit_sector_works_with_valid_worker() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        let sector_number = 1;
+        assert_ok!(create_miner_for(owner));
+
+        System::set_block_number(10);
+        assert_ok!(Miner::pre_commit_sector(
+            Origin::signed(WORKER),
+            FIRST_MINER_ADDR,
+            pre_commit_info(sector_number, 5)
+        ));
+
+        let pre_commit = Miner::pre_committed_sectors(FIRST_MINER_ADDR, sector_number).unwrap();
+        assert_eq!(pre_commit.info, pre_commit_info(sector_number, 5));
+        assert_eq!(pre_commit.pre_commit_epoch, 10);
+        assert_eq!(
+            System::events()
+                .pop()
+                .map(|e| e.event)
+                .expect("EventRecord should have event field"),
+            Event::pallet_miner(pallet_miner::Event::SectorPreCommitted(
+                FIRST_MINER_ADDR,
+                sector_number
+            ))
+        )
+    });
+}
+
+#[test]
+fn pre_commit_sector_works_with_valid_controller() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        let controller: u64 = 3;
+        assert_ok!(create_miner_for(owner));
+        assert_ok!(Miner::change_worker_address(
+            Origin::signed(owner),
+            FIRST_MINER_ADDR,
+            WORKER,
+            MinerControllers::Override(vec![controller])
+        ));
+
+        System::set_block_number(10);
+        assert_ok!(Miner::pre_commit_sector(
+            Origin::signed(controller),
+            FIRST_MINER_ADDR,
+            pre_commit_info(1, 5)
+        ));
+        assert!(Miner::pre_committed_sectors(FIRST_MINER_ADDR, 1).is_some());
+    });
+}
+
+#[test]
+fn pre_commit_sector_rejects_invalid_signer() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        assert_ok!(create_miner_for(owner));
+
+        System::set_block_number(10);
+        // owner is not allowed to submit sector proofs, only worker and controllers
+        assert_noop!(
+            Miner::pre_commit_sector(
+                Origin::signed(owner),
+                FIRST_MINER_ADDR,
+                pre_commit_info(1, 5)
+            ),
+            Error::<Test>::InvalidSigner
+        );
+    });
+}
+
+#[test]
+fn pre_commit_sector_rejects_unknown_miner() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(10);
+        assert_noop!(
+            Miner::pre_commit_sector(
+                Origin::signed(WORKER),
+                FIRST_MINER_ADDR,
+                pre_commit_info(1, 5)
+            ),
+            Error::<Test>::NoSuchMiner
+        );
+    });
+}
+
+#[test]
+fn pre_commit_sector_rejects_duplicate_sector_number() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        assert_ok!(create_miner_for(owner));
+
+        System::set_block_number(10);
+        assert_ok!(Miner::pre_commit_sector(
+            Origin::signed(WORKER),
+            FIRST_MINER_ADDR,
+            pre_commit_info(1, 5)
+        ));
+        assert_noop!(
+            Miner::pre_commit_sector(
+                Origin::signed(WORKER),
+                FIRST_MINER_ADDR,
+                pre_commit_info(1, 6)
+            ),
+            Error::<Test>::SectorNumberAlreadyUsed
+        );
+    });
+}
+
+#[test]
+fn pre_commit_sector_rejects_expired_or_future_randomness() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        let lookback = <Test as pallet_miner::Config>::MaxPreCommitRandomnessLookback::get();
+        assert_ok!(create_miner_for(owner));
+
+        let block = 200;
+        System::set_block_number(block);
+        assert_noop!(
+            Miner::pre_commit_sector(
+                Origin::signed(WORKER),
+                FIRST_MINER_ADDR,
+                pre_commit_info(1, block - lookback - 1)
+            ),
+            Error::<Test>::SealRandomnessExpired
+        );
+        assert_noop!(
+            Miner::pre_commit_sector(
+                Origin::signed(WORKER),
+                FIRST_MINER_ADDR,
+                pre_commit_info(1, block)
+            ),
+            Error::<Test>::InvalidSealRandomness
+        );
+    });
+}
+
+#[test]
+fn pre_commit_sector_rejects_other_seal_proof_types() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        assert_ok!(create_miner_for(owner));
+        System::set_block_number(10);
+
+        assert_noop!(
+            Miner::pre_commit_sector(
+                Origin::signed(WORKER),
+                FIRST_MINER_ADDR,
+                SectorPreCommitInfo {
+                    seal_proof: RegisteredSealProof::StackedDrg8MiBV1,
+                    ..pre_commit_info(1, 5)
+                }
+            ),
+            Error::<Test>::InvalidSealProof
+        );
+    });
+}
+
+#[test]
+fn prove_commit_sector_activates_sector_and_adds_power() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        let sector_number = 1;
+        create_miner_with_pre_commit(owner, sector_number);
+
+        System::set_block_number(21);
+        assert_ok!(Miner::prove_commit_sector(
+            Origin::signed(WORKER),
+            FIRST_MINER_ADDR,
+            sector_number,
+            seal_proof(sector_number)
+        ));
+
+        let sector = Miner::sectors(FIRST_MINER_ADDR, sector_number).unwrap();
+        let sector_size = RegisteredSealProof::StackedDrg2KiBV1.sector_size() as u128;
+        assert_eq!(sector.activation, 21);
+        assert_eq!(sector.expiration, 1_000);
+        assert!(Miner::pre_committed_sectors(FIRST_MINER_ADDR, sector_number).is_none());
+        assert_eq!(
+            Power::claims(FIRST_MINER_ADDR),
+            Some(Claim {
+                raw_bytes_power: sector_size,
+                quality_adjusted_power: sector_size,
+            })
+        );
+        assert_eq!(
+            System::events()
+                .pop()
+                .map(|e| e.event)
+                .expect("EventRecord should have event field"),
+            Event::pallet_miner(pallet_miner::Event::SectorProven(
+                FIRST_MINER_ADDR,
+                sector_number
+            ))
+        )
+    });
+}
+
+#[test]
+fn prove_commit_sector_rejects_proof_before_challenge_delay() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_pre_commit(owner, 1);
+
+        System::set_block_number(20);
+        assert_noop!(
+            Miner::prove_commit_sector(Origin::signed(WORKER), FIRST_MINER_ADDR, 1, seal_proof(1)),
+            Error::<Test>::ProveCommitTooEarly
+        );
+    });
+}
+
+#[test]
+fn prove_commit_sector_rejects_proof_after_max_duration() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_pre_commit(owner, 1);
+
+        System::set_block_number(61);
+        assert_noop!(
+            Miner::prove_commit_sector(Origin::signed(WORKER), FIRST_MINER_ADDR, 1, seal_proof(1)),
+            Error::<Test>::ProveCommitExpired
+        );
+    });
+}
+
+#[test]
+fn prove_commit_sector_rejects_invalid_proof() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_pre_commit(owner, 1);
+
+        System::set_block_number(21);
+        assert_noop!(
+            Miner::prove_commit_sector(Origin::signed(WORKER), FIRST_MINER_ADDR, 1, vec![0]),
+            Error::<Test>::InvalidProof
+        );
+        assert_eq!(Power::claims(FIRST_MINER_ADDR), Some(Claim::default()));
+    });
+}
+
+#[test]
+fn prove_commit_sector_rejects_unknown_pre_commit() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_pre_commit(owner, 1);
+
+        System::set_block_number(21);
+        assert_noop!(
+            Miner::prove_commit_sector(Origin::signed(WORKER), FIRST_MINER_ADDR, 2, seal_proof(2)),
+            Error::<Test>::NoSuchPreCommit
+        );
+    });
+}
+
+#[test]
+fn create_miner_schedules_first_deadline() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        assert_ok!(create_miner_for(owner));
+
+        // miner index 1 is used as proving period offset
+        let miner_state = Miner::miner_states(FIRST_MINER_ADDR).unwrap();
+        assert_eq!(miner_state.proving_period_start, 1);
+        assert_eq!(miner_state.current_deadline, 0);
+
+        let deadline_info = Miner::current_deadline(&FIRST_MINER_ADDR).unwrap();
+        assert_eq!(deadline_info.open, 1);
+        assert_eq!(deadline_info.close, 11);
+        assert_eq!(Miner::deadline_cron(11), vec![FIRST_MINER_ADDR]);
+    });
+}
+
+#[test]
+fn prove_commit_sector_activates_deals_with_quality_adjusted_power() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        // Deals fill half of the sector space time from block 21 to 1_000
+        let sector_size = RegisteredSealProof::StackedDrg2KiBV1.sector_size() as u128;
+        let deal_weight = sector_size * 979 / 2;
+        set_deal_weight(1, deal_weight / 2, false);
+        set_deal_weight(2, deal_weight / 2, false);
+        create_miner_with_sector_info(
+            owner,
+            SectorPreCommitInfo {
+                deal_ids: vec![1, 2],
+                ..pre_commit_info(1, 5)
+            },
+        );
+
+        assert_eq!(activated_deals(), vec![(1, 21), (2, 21)]);
+        assert_eq!(
+            Miner::sectors(FIRST_MINER_ADDR, 1).unwrap().deal_weight,
+            deal_weight
+        );
+        // Half of the sector has the base multiplier and half twice the base multiplier
+        assert_eq!(
+            Power::claims(FIRST_MINER_ADDR),
+            Some(Claim {
+                raw_bytes_power: sector_size,
+                quality_adjusted_power: sector_size * 3 / 2,
+            })
+        );
+    });
+}
+
+#[test]
+fn verified_deals_earn_verified_quality_multiplier() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        // Deals and verified deals each fill a quarter of the sector space time
+        let sector_size = RegisteredSealProof::StackedDrg2KiBV1.sector_size() as u128;
+        let quarter_weight = sector_size * 979 / 4;
+        set_deal_weight(1, quarter_weight, false);
+        set_deal_weight(2, quarter_weight, true);
+        create_miner_with_sector_info(
+            owner,
+            SectorPreCommitInfo {
+                deal_ids: vec![1, 2],
+                ..pre_commit_info(1, 5)
+            },
+        );
+
+        let sector = Miner::sectors(FIRST_MINER_ADDR, 1).unwrap();
+        assert_eq!(sector.deal_weight, quarter_weight);
+        assert_eq!(sector.verified_deal_weight, quarter_weight);
+        // Half of the sector has the base multiplier, a quarter twice and a quarter ten times
+        // the base multiplier
+        assert_eq!(
+            Power::claims(FIRST_MINER_ADDR),
+            Some(Claim {
+                raw_bytes_power: sector_size,
+                quality_adjusted_power: sector_size * 7 / 2,
+            })
+        );
+    });
+}
+
+#[test]
+fn pre_commit_and_prove_commit_reject_invalid_deals() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        assert_ok!(create_miner_for(owner));
+        System::set_block_number(10);
+        assert_noop!(
+            Miner::pre_commit_sector(
+                Origin::signed(WORKER),
+                FIRST_MINER_ADDR,
+                SectorPreCommitInfo {
+                    deal_ids: vec![1],
+                    ..pre_commit_info(1, 5)
+                }
+            ),
+            DispatchError::Other("NoSuchDeal")
+        );
+
+        // The deal is activated in another sector before this one is proven
+        set_deal_weight(1, 100_000, false);
+        assert_ok!(Miner::pre_commit_sector(
+            Origin::signed(WORKER),
+            FIRST_MINER_ADDR,
+            SectorPreCommitInfo {
+                deal_ids: vec![1],
+                ..pre_commit_info(1, 5)
+            }
+        ));
+        assert_ok!(Miner::pre_commit_sector(
+            Origin::signed(WORKER),
+            FIRST_MINER_ADDR,
+            SectorPreCommitInfo {
+                deal_ids: vec![1],
+                ..pre_commit_info(2, 5)
+            }
+        ));
+        run_to_block(21);
+        assert_ok!(Miner::prove_commit_sector(
+            Origin::signed(WORKER),
+            FIRST_MINER_ADDR,
+            1,
+            seal_proof(1)
+        ));
+        assert_noop!(
+            Miner::prove_commit_sector(Origin::signed(WORKER), FIRST_MINER_ADDR, 2, seal_proof(2)),
+            DispatchError::Other("DealAlreadyActivated")
+        );
+    });
+}
+
+#[test]
+fn prove_commit_sector_assigns_sectors_to_deadlines_and_partitions() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_pre_commit(owner, 1);
+        for sector_number in 2..=6 {
+            assert_ok!(Miner::pre_commit_sector(
+                Origin::signed(WORKER),
+                FIRST_MINER_ADDR,
+                pre_commit_info(sector_number, 5)
+            ));
+        }
+
+        // no hooks are run, deadline 0 stays open and no sectors are assigned to it
+        System::set_block_number(21);
+        for sector_number in 1..=6 {
+            assert_ok!(Miner::prove_commit_sector(
+                Origin::signed(WORKER),
+                FIRST_MINER_ADDR,
+                sector_number,
+                seal_proof(sector_number)
+            ));
+        }
+
+        let sector_1 = Miner::sectors(FIRST_MINER_ADDR, 1).unwrap();
+        let sector_5 = Miner::sectors(FIRST_MINER_ADDR, 5).unwrap();
+        let sector_6 = Miner::sectors(FIRST_MINER_ADDR, 6).unwrap();
+        assert_eq!((sector_1.deadline, sector_1.partition), (1, 0));
+        assert_eq!((sector_5.deadline, sector_5.partition), (5, 0));
+        assert_eq!((sector_6.deadline, sector_6.partition), (1, 0));
+        assert_eq!(Miner::deadlines(FIRST_MINER_ADDR, 0).sector_count(), 0);
+        assert_eq!(
+            Miner::deadlines(FIRST_MINER_ADDR, 1).partitions[0]
+                .sectors
+                .iter()
+                .copied()
+                .collect::<Vec<_>>(),
+            vec![1, 6]
+        );
+    });
+}
+
+#[test]
+fn submit_windowed_post_records_proven_partitions() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector(owner, 1);
+        let deadline = Miner::sectors(FIRST_MINER_ADDR, 1).unwrap().deadline;
+
+        run_to_deadline_open(deadline);
+        assert_ok!(Miner::submit_windowed_post(
+            Origin::signed(WORKER),
+            FIRST_MINER_ADDR,
+            deadline,
+            vec![0],
+            window_post_proof(deadline, &[0])
+        ));
+
+        assert!(Miner::deadlines(FIRST_MINER_ADDR, deadline)
+            .post_submissions
+            .contains(&0));
+        assert_eq!(
+            System::events()
+                .pop()
+                .map(|e| e.event)
+                .expect("EventRecord should have event field"),
+            Event::pallet_miner(pallet_miner::Event::PoStSubmitted(
+                FIRST_MINER_ADDR,
+                deadline,
+                vec![0]
+            ))
+        )
+    });
+}
+
+#[test]
+fn submit_windowed_post_rejects_deadline_that_is_not_open() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector(owner, 1);
+        let deadline = Miner::sectors(FIRST_MINER_ADDR, 1).unwrap().deadline;
+
+        run_to_deadline_open(deadline);
+        assert_noop!(
+            Miner::submit_windowed_post(
+                Origin::signed(WORKER),
+                FIRST_MINER_ADDR,
+                deadline + 1,
+                vec![0],
+                window_post_proof(deadline + 1, &[0])
+            ),
+            Error::<Test>::InvalidDeadline
+        );
+    });
+}
+
+#[test]
+fn submit_windowed_post_rejects_invalid_partitions_and_proofs() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector(owner, 1);
+        let deadline = Miner::sectors(FIRST_MINER_ADDR, 1).unwrap().deadline;
+
+        run_to_deadline_open(deadline);
+        assert_noop!(
+            Miner::submit_windowed_post(
+                Origin::signed(WORKER),
+                FIRST_MINER_ADDR,
+                deadline,
+                vec![],
+                window_post_proof(deadline, &[])
+            ),
+            Error::<Test>::NoPartitions
+        );
+        assert_noop!(
+            Miner::submit_windowed_post(
+                Origin::signed(WORKER),
+                FIRST_MINER_ADDR,
+                deadline,
+                vec![1],
+                window_post_proof(deadline, &[1])
+            ),
+            Error::<Test>::NoSuchPartition
+        );
+        assert_noop!(
+            Miner::submit_windowed_post(
+                Origin::signed(WORKER),
+                FIRST_MINER_ADDR,
+                deadline,
+                vec![0],
+                vec![0]
+            ),
+            Error::<Test>::InvalidProof
+        );
+
+        assert_ok!(Miner::submit_windowed_post(
+            Origin::signed(WORKER),
+            FIRST_MINER_ADDR,
+            deadline,
+            vec![0],
+            window_post_proof(deadline, &[0])
+        ));
+        assert_noop!(
+            Miner::submit_windowed_post(
+                Origin::signed(WORKER),
+                FIRST_MINER_ADDR,
+                deadline,
+                vec![0],
+                window_post_proof(deadline, &[0])
+            ),
+            Error::<Test>::PartitionAlreadyProven
+        );
+    });
+}
+
+#[test]
+fn deadline_end_advances_deadline_and_proving_period() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        let proving_period = <Test as pallet_miner::Config>::WPoStProvingPeriod::get();
+        create_miner_with_sector(owner, 1);
+        let deadline = Miner::sectors(FIRST_MINER_ADDR, 1).unwrap().deadline;
+
+        run_to_deadline_open(deadline);
+        let deadline_info = Miner::current_deadline(&FIRST_MINER_ADDR).unwrap();
+        assert_ok!(Miner::submit_windowed_post(
+            Origin::signed(WORKER),
+            FIRST_MINER_ADDR,
+            deadline,
+            vec![0],
+            window_post_proof(deadline, &[0])
+        ));
+
+        run_to_block(deadline_info.close);
+        assert!(Miner::deadlines(FIRST_MINER_ADDR, deadline)
+            .post_submissions
+            .is_empty());
+        assert_eq!(
+            Miner::current_deadline(&FIRST_MINER_ADDR).unwrap().index,
+            deadline + 1
+        );
+
+        let proving_period_start = Miner::miner_states(FIRST_MINER_ADDR)
+            .unwrap()
+            .proving_period_start;
+        run_to_deadline_open(deadline);
+        assert_eq!(
+            Miner::miner_states(FIRST_MINER_ADDR)
+                .unwrap()
+                .proving_period_start,
+            proving_period_start + proving_period
+        );
+    });
+}
+
+#[test]
+fn missed_window_post_faults_sectors_and_removes_power() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector(owner, 1);
+        let deadline = Miner::sectors(FIRST_MINER_ADDR, 1).unwrap().deadline;
+
+        run_to_deadline_open(deadline);
+        let deadline_close = Miner::current_deadline(&FIRST_MINER_ADDR).unwrap().close;
+        run_to_block(deadline_close);
+
+        let partition = &Miner::deadlines(FIRST_MINER_ADDR, deadline).partitions[0];
+        assert_eq!(partition.faults.get(&1), Some(&deadline_close));
+        assert_eq!(Power::claims(FIRST_MINER_ADDR), Some(Claim::default()));
+        assert!(System::events().iter().any(|record| record.event
+            == Event::pallet_miner(pallet_miner::Event::SectorsFaulted(
+                FIRST_MINER_ADDR,
+                vec![1]
+            ))));
+    });
+}
+
+#[test]
+fn proven_deadline_keeps_sectors_active() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector(owner, 1);
+        let deadline = Miner::sectors(FIRST_MINER_ADDR, 1).unwrap().deadline;
+        let claim = Power::claims(FIRST_MINER_ADDR);
+
+        run_to_deadline_open(deadline);
+        let deadline_close = Miner::current_deadline(&FIRST_MINER_ADDR).unwrap().close;
+        assert_ok!(Miner::submit_windowed_post(
+            Origin::signed(WORKER),
+            FIRST_MINER_ADDR,
+            deadline,
+            vec![0],
+            window_post_proof(deadline, &[0])
+        ));
+        run_to_block(deadline_close);
+
+        assert!(Miner::deadlines(FIRST_MINER_ADDR, deadline).partitions[0]
+            .faults
+            .is_empty());
+        assert_eq!(Power::claims(FIRST_MINER_ADDR), claim);
+    });
+}
+
+fn fault_declaration(deadline: DeadlineIndex, sectors: Vec<SectorNumber>) -> FaultDeclaration {
+    FaultDeclaration {
+        deadline,
+        partition: 0,
+        sectors,
+    }
+}
+
+#[test]
+fn declare_faults_faults_sectors_and_removes_power() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector(owner, 1);
+        let deadline = Miner::sectors(FIRST_MINER_ADDR, 1).unwrap().deadline;
+
+        assert_ok!(Miner::declare_faults(
+            Origin::signed(owner),
+            FIRST_MINER_ADDR,
+            vec![fault_declaration(deadline, vec![1])]
+        ));
+
+        let partition = &Miner::deadlines(FIRST_MINER_ADDR, deadline).partitions[0];
+        assert_eq!(partition.faults.get(&1), Some(&21));
+        assert_eq!(Power::claims(FIRST_MINER_ADDR), Some(Claim::default()));
+        assert_eq!(
+            System::events().pop().map(|e| e.event),
+            Some(Event::pallet_miner(pallet_miner::Event::FaultsDeclared(
+                FIRST_MINER_ADDR,
+                vec![1]
+            )))
+        );
+
+        // Declaring the fault again does not remove the power twice
+        assert_ok!(Miner::declare_faults(
+            Origin::signed(WORKER),
+            FIRST_MINER_ADDR,
+            vec![fault_declaration(deadline, vec![1])]
+        ));
+        assert_eq!(Power::claims(FIRST_MINER_ADDR), Some(Claim::default()));
+    });
+}
+
+#[test]
+fn declare_faults_rejects_invalid_declarations() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector(owner, 1);
+        let deadline = Miner::sectors(FIRST_MINER_ADDR, 1).unwrap().deadline;
+        let current_deadline = Miner::current_deadline(&FIRST_MINER_ADDR).unwrap().index;
+
+        assert_noop!(
+            Miner::declare_faults(
+                Origin::signed(2),
+                FIRST_MINER_ADDR,
+                vec![fault_declaration(deadline, vec![1])]
+            ),
+            Error::<Test>::InvalidSigner
+        );
+        assert_noop!(
+            Miner::declare_faults(
+                Origin::signed(owner),
+                FIRST_MINER_ADDR,
+                vec![fault_declaration(current_deadline, vec![1])]
+            ),
+            Error::<Test>::DeadlineImmutable
+        );
+        assert_noop!(
+            Miner::declare_faults(
+                Origin::signed(owner),
+                FIRST_MINER_ADDR,
+                vec![fault_declaration(deadline, vec![2])]
+            ),
+            Error::<Test>::NoSuchSector
+        );
+        assert_noop!(
+            Miner::declare_faults(
+                Origin::signed(owner),
+                FIRST_MINER_ADDR,
+                vec![FaultDeclaration {
+                    deadline,
+                    partition: 1,
+                    sectors: vec![1],
+                }]
+            ),
+            Error::<Test>::NoSuchPartition
+        );
+        // The mock allows 3 declarations and 5 sectors per call
+        assert_noop!(
+            Miner::declare_faults(
+                Origin::signed(owner),
+                FIRST_MINER_ADDR,
+                vec![fault_declaration(deadline, vec![1]); 4]
+            ),
+            Error::<Test>::TooManyDeclarations
+        );
+        assert_noop!(
+            Miner::declare_faults_recovered(
+                Origin::signed(owner),
+                FIRST_MINER_ADDR,
+                vec![fault_declaration(deadline, vec![1; 6])]
+            ),
+            Error::<Test>::TooManyDeclaredSectors
+        );
+    });
+}
+
+#[test]
+fn declare_faults_recovered_rejects_sectors_that_are_not_faulty() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector(owner, 1);
+        let deadline = Miner::sectors(FIRST_MINER_ADDR, 1).unwrap().deadline;
+
+        assert_noop!(
+            Miner::declare_faults_recovered(
+                Origin::signed(owner),
+                FIRST_MINER_ADDR,
+                vec![fault_declaration(deadline, vec![1])]
+            ),
+            Error::<Test>::SectorNotFaulty
+        );
+    });
+}
+
+#[test]
+fn recovered_sectors_regain_power_after_window_post() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector(owner, 1);
+        let deadline = Miner::sectors(FIRST_MINER_ADDR, 1).unwrap().deadline;
+        let claim = Power::claims(FIRST_MINER_ADDR);
+
+        assert_ok!(Miner::declare_faults(
+            Origin::signed(owner),
+            FIRST_MINER_ADDR,
+            vec![fault_declaration(deadline, vec![1])]
+        ));
+        assert_ok!(Miner::declare_faults_recovered(
+            Origin::signed(owner),
+            FIRST_MINER_ADDR,
+            vec![fault_declaration(deadline, vec![1])]
+        ));
+        assert_eq!(
+            System::events().pop().map(|e| e.event),
+            Some(Event::pallet_miner(
+                pallet_miner::Event::RecoveriesDeclared(FIRST_MINER_ADDR, vec![1])
+            ))
+        );
+        // The sector stays unpowered until it is proven
+        assert_eq!(Power::claims(FIRST_MINER_ADDR), Some(Claim::default()));
+
+        run_to_deadline_open(deadline);
+        assert_ok!(Miner::submit_windowed_post(
+            Origin::signed(WORKER),
+            FIRST_MINER_ADDR,
+            deadline,
+            vec![0],
+            window_post_proof(deadline, &[0])
+        ));
+
+        let partition = &Miner::deadlines(FIRST_MINER_ADDR, deadline).partitions[0];
+        assert!(partition.faults.is_empty());
+        assert!(partition.recoveries.is_empty());
+        assert_eq!(Power::claims(FIRST_MINER_ADDR), claim);
+        assert_eq!(
+            System::events().pop().map(|e| e.event),
+            Some(Event::pallet_miner(pallet_miner::Event::SectorsRecovered(
+                FIRST_MINER_ADDR,
+                vec![1]
+            )))
+        );
+    });
+}
+
+#[test]
+fn missed_window_post_retracts_recoveries() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector(owner, 1);
+        let deadline = Miner::sectors(FIRST_MINER_ADDR, 1).unwrap().deadline;
+
+        assert_ok!(Miner::declare_faults(
+            Origin::signed(owner),
+            FIRST_MINER_ADDR,
+            vec![fault_declaration(deadline, vec![1])]
+        ));
+        assert_ok!(Miner::declare_faults_recovered(
+            Origin::signed(owner),
+            FIRST_MINER_ADDR,
+            vec![fault_declaration(deadline, vec![1])]
+        ));
+
+        run_to_deadline_open(deadline);
+        let deadline_close = Miner::current_deadline(&FIRST_MINER_ADDR).unwrap().close;
+        run_to_block(deadline_close);
+
+        let partition = &Miner::deadlines(FIRST_MINER_ADDR, deadline).partitions[0];
+        assert_eq!(partition.faults.get(&1), Some(&21));
+        assert!(partition.recoveries.is_empty());
+        assert_eq!(Power::claims(FIRST_MINER_ADDR), Some(Claim::default()));
+    });
+}
+
+#[test]
+fn prove_commit_sector_records_expected_block_reward() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector(owner, 1);
+
+        // The only sector of the network is expected to earn the whole block reward
+        assert_eq!(
+            Miner::sectors(FIRST_MINER_ADDR, 1)
+                .unwrap()
+                .expected_block_reward,
+            1_000
+        );
+    });
+}
+
+#[test]
+fn terminate_sectors_removes_sectors_and_charges_penalty() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector(owner, 1);
+        let deadline = Miner::sectors(FIRST_MINER_ADDR, 1).unwrap().deadline;
+        run_to_block(31);
+
+        assert_ok!(Miner::terminate_sectors(
+            Origin::signed(owner),
+            FIRST_MINER_ADDR,
+            vec![fault_declaration(deadline, vec![1])]
+        ));
+
+        assert!(Miner::sectors(FIRST_MINER_ADDR, 1).is_none());
+        assert!(Miner::deadlines(FIRST_MINER_ADDR, deadline).partitions[0]
+            .sectors
+            .is_empty());
+        assert_eq!(Power::claims(FIRST_MINER_ADDR), Some(Claim::default()));
+        // The penalty is slashed from the pledge of the sector and the rest is released
+        assert_eq!(Balances::reserved_balance(FIRST_MINER_ADDR), 0);
+        assert_eq!(
+            Balances::free_balance(FIRST_MINER_ADDR),
+            MINER_BALANCE - 5_000
+        );
+        assert_eq!(
+            Miner::miner_states(FIRST_MINER_ADDR)
+                .unwrap()
+                .initial_pledge,
+            0
+        );
+        // Half of the expected block reward for each of the 10 blocks the sector was active
+        assert_eq!(
+            System::events().pop().map(|e| e.event),
+            Some(Event::pallet_miner(pallet_miner::Event::SectorsTerminated(
+                FIRST_MINER_ADDR,
+                vec![1],
+                5_000
+            )))
+        );
+    });
+}
+
+#[test]
+fn terminate_sectors_slashes_deals() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        set_deal_weight(1, 100_000, false);
+        set_deal_weight(2, 100_000, false);
+        create_miner_with_sector_info(
+            owner,
+            SectorPreCommitInfo {
+                deal_ids: vec![1, 2],
+                ..pre_commit_info(1, 5)
+            },
+        );
+        let deadline = Miner::sectors(FIRST_MINER_ADDR, 1).unwrap().deadline;
+        run_to_block(31);
+
+        assert_ok!(Miner::terminate_sectors(
+            Origin::signed(owner),
+            FIRST_MINER_ADDR,
+            vec![fault_declaration(deadline, vec![1])]
+        ));
+
+        assert_eq!(terminated_deals(), vec![(1, 31), (2, 31)]);
+    });
+}
+
+#[test]
+fn terminate_sectors_rejects_invalid_declarations() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector(owner, 1);
+        let deadline = Miner::sectors(FIRST_MINER_ADDR, 1).unwrap().deadline;
+        let current_deadline = Miner::current_deadline(&FIRST_MINER_ADDR).unwrap().index;
+
+        assert_noop!(
+            Miner::terminate_sectors(
+                Origin::signed(2),
+                FIRST_MINER_ADDR,
+                vec![fault_declaration(deadline, vec![1])]
+            ),
+            Error::<Test>::InvalidSigner
+        );
+        assert_noop!(
+            Miner::terminate_sectors(
+                Origin::signed(owner),
+                FIRST_MINER_ADDR,
+                vec![fault_declaration(current_deadline, vec![1])]
+            ),
+            Error::<Test>::DeadlineImmutable
+        );
+        assert_noop!(
+            Miner::terminate_sectors(
+                Origin::signed(owner),
+                FIRST_MINER_ADDR,
+                vec![fault_declaration(deadline, vec![2])]
+            ),
+            Error::<Test>::NoSuchSector
+        );
+        assert_noop!(
+            Miner::terminate_sectors(
+                Origin::signed(owner),
+                FIRST_MINER_ADDR,
+                vec![
+                    fault_declaration(deadline, vec![1, 1, 1]),
+                    fault_declaration(deadline, vec![1, 1, 1])
+                ]
+            ),
+            Error::<Test>::TooManyDeclaredSectors
+        );
+    });
+}
+
+#[test]
+fn terminate_sectors_does_not_remove_power_of_faulty_sectors_twice() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector(owner, 1);
+        add_sector(2);
+        let deadline = Miner::sectors(FIRST_MINER_ADDR, 1).unwrap().deadline;
+        let claim = Power::claims(FIRST_MINER_ADDR);
+
+        assert_ok!(Miner::declare_faults(
+            Origin::signed(owner),
+            FIRST_MINER_ADDR,
+            vec![fault_declaration(deadline, vec![1])]
+        ));
+        assert_ok!(Miner::terminate_sectors(
+            Origin::signed(owner),
+            FIRST_MINER_ADDR,
+            vec![fault_declaration(deadline, vec![1, 1])]
+        ));
+
+        // Only the power of sector 1 was removed, once
+        assert_eq!(
+            Power::claims(FIRST_MINER_ADDR).map(|c| c.raw_bytes_power),
+            claim.map(|c| c.raw_bytes_power / 2)
+        );
+    });
+}
+
+#[test]
+fn sectors_faulty_for_too_long_are_terminated() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector(owner, 1);
+        let deadline = Miner::sectors(FIRST_MINER_ADDR, 1).unwrap().deadline;
+
+        // The sector misses its deadline and becomes faulty
+        run_to_deadline_open(deadline);
+        let faulted_at = Miner::current_deadline(&FIRST_MINER_ADDR).unwrap().close;
+        run_to_block(faulted_at);
+        assert!(Miner::sectors(FIRST_MINER_ADDR, 1).is_some());
+
+        // It is still faulty when the deadline ends `MaxFaultDuration` blocks later
+        run_to_block(faulted_at + 60);
+        assert!(Miner::sectors(FIRST_MINER_ADDR, 1).is_none());
+        assert!(Miner::deadlines(FIRST_MINER_ADDR, deadline).partitions[0]
+            .sectors
+            .is_empty());
+        // The age of the sector is capped at `TerminationLifetimeCap`
+        assert!(System::events().iter().any(|record| record.event
+            == Event::pallet_miner(pallet_miner::Event::SectorsTerminated(
+                FIRST_MINER_ADDR,
+                vec![1],
+                10_000
+            ))));
+        // Two fault fees were slashed from the pledge before the penalty, the part of the
+        // penalty not covered by the remaining pledge is slashed from the free balance
+        assert_eq!(Balances::reserved_balance(FIRST_MINER_ADDR), 0);
+        assert_eq!(
+            Balances::free_balance(FIRST_MINER_ADDR),
+            MINER_BALANCE - 2 * 2_000 - 10_000
+        );
+    });
+}
+
+#[test]
+fn pre_commit_sector_rejects_lifetime_above_maximum() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        assert_ok!(create_miner_for(owner));
+        System::set_block_number(10);
+
+        assert_noop!(
+            Miner::pre_commit_sector(
+                Origin::signed(WORKER),
+                FIRST_MINER_ADDR,
+                SectorPreCommitInfo {
+                    expiration: 2_011,
+                    ..pre_commit_info(1, 5)
+                }
+            ),
+            Error::<Test>::SectorLifetimeTooLong
+        );
+    });
+}
+
+#[test]
+fn prove_commit_sector_rejects_expired_pre_commit() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        assert_ok!(create_miner_for(owner));
+        System::set_block_number(10);
+        assert_ok!(Miner::pre_commit_sector(
+            Origin::signed(WORKER),
+            FIRST_MINER_ADDR,
+            SectorPreCommitInfo {
+                expiration: 15,
+                ..pre_commit_info(1, 5)
+            }
+        ));
+        run_to_block(21);
+
+        assert_noop!(
+            Miner::prove_commit_sector(Origin::signed(WORKER), FIRST_MINER_ADDR, 1, seal_proof(1)),
+            Error::<Test>::InvalidExpiration
+        );
+    });
+}
+
+#[test]
+fn expired_sectors_are_removed_with_their_power() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector_info(
+            owner,
+            SectorPreCommitInfo {
+                expiration: 50,
+                ..pre_commit_info(1, 5)
+            },
+        );
+        let deadline = Miner::sectors(FIRST_MINER_ADDR, 1).unwrap().deadline;
+        assert_eq!(Miner::sector_expirations(50), vec![(FIRST_MINER_ADDR, 1)]);
+
+        run_to_block(49);
+        assert!(Miner::sectors(FIRST_MINER_ADDR, 1).is_some());
+
+        run_to_block(50);
+        assert!(Miner::sectors(FIRST_MINER_ADDR, 1).is_none());
+        assert!(Miner::sector_expirations(50).is_empty());
+        assert!(Miner::deadlines(FIRST_MINER_ADDR, deadline).partitions[0]
+            .sectors
+            .is_empty());
+        assert_eq!(Power::claims(FIRST_MINER_ADDR), Some(Claim::default()));
+        assert_eq!(
+            System::events().pop().map(|e| e.event),
+            Some(Event::pallet_miner(pallet_miner::Event::SectorExpired(
+                FIRST_MINER_ADDR,
+                1
+            )))
+        );
+    });
+}
+
+#[test]
+fn extend_sector_expiration_postpones_expiration() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector_info(
+            owner,
+            SectorPreCommitInfo {
+                expiration: 50,
+                ..pre_commit_info(1, 5)
+            },
+        );
+        let claim = Power::claims(FIRST_MINER_ADDR);
+
+        assert_ok!(Miner::extend_sector_expiration(
+            Origin::signed(WORKER),
+            FIRST_MINER_ADDR,
+            1,
+            60
+        ));
+        assert_eq!(Miner::sectors(FIRST_MINER_ADDR, 1).unwrap().expiration, 60);
+        assert_eq!(Power::claims(FIRST_MINER_ADDR), claim);
+        assert_eq!(
+            System::events().pop().map(|e| e.event),
+            Some(Event::pallet_miner(
+                pallet_miner::Event::SectorExpirationExtended(FIRST_MINER_ADDR, 1, 60)
+            ))
+        );
+
+        // The entry at the previous expiration is skipped
+        run_to_block(50);
+        assert!(Miner::sectors(FIRST_MINER_ADDR, 1).is_some());
+        assert_eq!(Power::claims(FIRST_MINER_ADDR), claim);
+
+        run_to_block(60);
+        assert!(Miner::sectors(FIRST_MINER_ADDR, 1).is_none());
+        assert_eq!(Power::claims(FIRST_MINER_ADDR), Some(Claim::default()));
+    });
+}
+
+#[test]
+fn extend_sector_expiration_rejects_invalid_extensions() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector(owner, 1);
+
+        assert_noop!(
+            Miner::extend_sector_expiration(Origin::signed(2), FIRST_MINER_ADDR, 1, 1_500),
+            Error::<Test>::InvalidSigner
+        );
+        assert_noop!(
+            Miner::extend_sector_expiration(Origin::signed(WORKER), FIRST_MINER_ADDR, 2, 1_500),
+            Error::<Test>::NoSuchSector
+        );
+        assert_noop!(
+            Miner::extend_sector_expiration(Origin::signed(WORKER), FIRST_MINER_ADDR, 1, 1_000),
+            Error::<Test>::InvalidExpiration
+        );
+        // Activated at block 21
+        assert_noop!(
+            Miner::extend_sector_expiration(Origin::signed(WORKER), FIRST_MINER_ADDR, 1, 2_022),
+            Error::<Test>::SectorLifetimeTooLong
+        );
+        assert_ok!(Miner::extend_sector_expiration(
+            Origin::signed(WORKER),
+            FIRST_MINER_ADDR,
+            1,
+            2_021
+        ));
+
+        let deadline = Miner::sectors(FIRST_MINER_ADDR, 1).unwrap().deadline;
+        assert_ok!(Miner::declare_faults(
+            Origin::signed(owner),
+            FIRST_MINER_ADDR,
+            vec![fault_declaration(deadline, vec![1])]
+        ));
+        assert_noop!(
+            Miner::extend_sector_expiration(Origin::signed(WORKER), FIRST_MINER_ADDR, 1, 2_021),
+            Error::<Test>::SectorFaulty
+        );
+    });
+}
+
+#[test]
+fn extend_sector_expiration_rejects_sectors_with_deals() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        set_deal_weight(1, 100_000, false);
+        create_miner_with_sector_info(
+            owner,
+            SectorPreCommitInfo {
+                deal_ids: vec![1],
+                ..pre_commit_info(1, 5)
+            },
+        );
+
+        assert_noop!(
+            Miner::extend_sector_expiration(Origin::signed(WORKER), FIRST_MINER_ADDR, 1, 1_500),
+            Error::<Test>::SectorHasDeals
+        );
+    });
+}
+
+#[test]
+fn prove_commit_sector_reserves_initial_pledge() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector(owner, 1);
+
+        // 10 blocks of the expected block reward of the sector
+        let sector = Miner::sectors(FIRST_MINER_ADDR, 1).unwrap();
+        assert_eq!(sector.initial_pledge, 10_000);
+        assert_eq!(
+            Miner::miner_states(FIRST_MINER_ADDR)
+                .unwrap()
+                .initial_pledge,
+            10_000
+        );
+        assert_eq!(Balances::reserved_balance(FIRST_MINER_ADDR), 10_000);
+        assert_eq!(
+            Balances::free_balance(FIRST_MINER_ADDR),
+            MINER_BALANCE - 10_000
+        );
+
+        // The second sector halves the expected reward of new sectors
+        add_sector(2);
+        assert_eq!(
+            Miner::sectors(FIRST_MINER_ADDR, 2).unwrap().initial_pledge,
+            5_000
+        );
+        assert_eq!(Balances::reserved_balance(FIRST_MINER_ADDR), 15_000);
+    });
+}
+
+#[test]
+fn pre_commit_and_prove_commit_require_funds_for_pledge() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_pre_commit(owner, 1);
+        // Less than the pledge of the sector once its 5_000 deposit is released
+        Balances::make_free_balance_be(&FIRST_MINER_ADDR, 4_000);
+
+        assert_noop!(
+            Miner::pre_commit_sector(
+                Origin::signed(WORKER),
+                FIRST_MINER_ADDR,
+                pre_commit_info(2, 5)
+            ),
+            Error::<Test>::InsufficientFunds
+        );
+
+        run_to_block(21);
+        assert_noop!(
+            Miner::prove_commit_sector(Origin::signed(WORKER), FIRST_MINER_ADDR, 1, seal_proof(1)),
+            Error::<Test>::InsufficientFunds
+        );
+        assert_eq!(Power::claims(FIRST_MINER_ADDR), Some(Claim::default()));
+    });
+}
+
+#[test]
+fn expired_sectors_release_their_pledge() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector_info(
+            owner,
+            SectorPreCommitInfo {
+                expiration: 50,
+                ..pre_commit_info(1, 5)
+            },
+        );
+        assert_eq!(Balances::reserved_balance(FIRST_MINER_ADDR), 10_000);
+
+        run_to_block(50);
+        assert_eq!(Balances::reserved_balance(FIRST_MINER_ADDR), 0);
+        assert_eq!(Balances::free_balance(FIRST_MINER_ADDR), MINER_BALANCE);
+        assert_eq!(
+            Miner::miner_states(FIRST_MINER_ADDR)
+                .unwrap()
+                .initial_pledge,
+            0
+        );
+    });
+}
+
+#[test]
+fn faulty_sectors_are_charged_a_fee_from_pledge() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector(owner, 1);
+        let deadline = Miner::sectors(FIRST_MINER_ADDR, 1).unwrap().deadline;
+
+        run_to_deadline_open(deadline);
+        let deadline_close = Miner::current_deadline(&FIRST_MINER_ADDR).unwrap().close;
+        run_to_block(deadline_close);
+
+        // 2 blocks of the expected block reward of the sector
+        assert!(System::events().iter().any(|record| record.event
+            == Event::pallet_miner(pallet_miner::Event::FaultFeeCharged(
+                FIRST_MINER_ADDR,
+                2_000
+            ))));
+        assert_eq!(
+            Miner::miner_states(FIRST_MINER_ADDR)
+                .unwrap()
+                .initial_pledge,
+            8_000
+        );
+        assert_eq!(Balances::reserved_balance(FIRST_MINER_ADDR), 8_000);
+        assert_eq!(
+            Balances::free_balance(FIRST_MINER_ADDR),
+            MINER_BALANCE - 10_000
+        );
+    });
+}
+
+#[test]
+fn pre_commit_sector_reserves_deposit() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_pre_commit(owner, 1);
+
+        // 5 blocks of the expected block reward of the sector
+        assert_eq!(
+            Miner::pre_committed_sectors(FIRST_MINER_ADDR, 1)
+                .unwrap()
+                .pre_commit_deposit,
+            5_000
+        );
+        assert_eq!(
+            Miner::miner_states(FIRST_MINER_ADDR)
+                .unwrap()
+                .pre_commit_deposits,
+            5_000
+        );
+        assert_eq!(Balances::reserved_balance(FIRST_MINER_ADDR), 5_000);
+        assert_eq!(
+            Miner::pre_commit_expirations(61),
+            vec![(FIRST_MINER_ADDR, 1)]
+        );
+    });
+}
+
+#[test]
+fn prove_commit_sector_releases_deposit() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector(owner, 1);
+
+        assert_eq!(
+            Miner::miner_states(FIRST_MINER_ADDR)
+                .unwrap()
+                .pre_commit_deposits,
+            0
+        );
+        // Only the pledge stays reserved
+        assert_eq!(Balances::reserved_balance(FIRST_MINER_ADDR), 10_000);
+
+        // The expiration entry of the proven pre-commit is skipped
+        run_to_block(61);
+        assert!(Miner::sectors(FIRST_MINER_ADDR, 1).is_some());
+        assert_eq!(Balances::reserved_balance(FIRST_MINER_ADDR), 10_000);
+    });
+}
+
+#[test]
+fn expired_pre_commit_forfeits_deposit() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_pre_commit(owner, 1);
+
+        run_to_block(60);
+        assert!(Miner::pre_committed_sectors(FIRST_MINER_ADDR, 1).is_some());
+
+        run_to_block(61);
+        assert!(Miner::pre_committed_sectors(FIRST_MINER_ADDR, 1).is_none());
+        assert!(Miner::pre_commit_expirations(61).is_empty());
+        assert_eq!(
+            Miner::miner_states(FIRST_MINER_ADDR)
+                .unwrap()
+                .pre_commit_deposits,
+            0
+        );
+        assert_eq!(Balances::reserved_balance(FIRST_MINER_ADDR), 0);
+        assert_eq!(
+            Balances::free_balance(FIRST_MINER_ADDR),
+            MINER_BALANCE - 5_000
+        );
+        assert_eq!(Balances::free_balance(TREASURY), 5_000);
+        assert!(System::events().iter().any(|record| record.event
+            == Event::pallet_miner(pallet_miner::Event::PreCommitExpired(
+                FIRST_MINER_ADDR,
+                1,
+                5_000
+            ))));
+    });
+}
+
+#[test]
+fn penalties_are_sent_to_slashed_handler() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector(owner, 1);
+        let deadline = Miner::sectors(FIRST_MINER_ADDR, 1).unwrap().deadline;
+        run_to_block(31);
+
+        assert_ok!(Miner::terminate_sectors(
+            Origin::signed(owner),
+            FIRST_MINER_ADDR,
+            vec![fault_declaration(deadline, vec![1])]
+        ));
+        assert_eq!(Balances::free_balance(TREASURY), 5_000);
+    });
+}
+
+#[test]
+fn withdraw_balance_transfers_available_balance_to_owner() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector(owner, 1);
+
+        assert_ok!(Miner::withdraw_balance(
+            Origin::signed(owner),
+            FIRST_MINER_ADDR,
+            1_000
+        ));
+        assert_eq!(Balances::free_balance(owner), 1_000);
+
+        // The withdrawn amount is capped at the balance that is not pledged
+        assert_ok!(Miner::withdraw_balance(
+            Origin::signed(owner),
+            FIRST_MINER_ADDR,
+            MINER_BALANCE
+        ));
+        assert_eq!(Balances::free_balance(owner), MINER_BALANCE - 10_000);
+        assert_eq!(Balances::free_balance(FIRST_MINER_ADDR), 0);
+        assert_eq!(Balances::reserved_balance(FIRST_MINER_ADDR), 10_000);
+        assert_eq!(
+            System::events().pop().map(|e| e.event),
+            Some(Event::pallet_miner(pallet_miner::Event::BalanceWithdrawn(
+                FIRST_MINER_ADDR,
+                owner,
+                MINER_BALANCE - 11_000
+            )))
+        );
+    });
+}
+
+#[test]
+fn withdraw_balance_rejects_invalid_signer_and_unknown_miner() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        assert_ok!(create_miner_for(owner));
+
+        assert_noop!(
+            Miner::withdraw_balance(Origin::signed(WORKER), FIRST_MINER_ADDR, 1_000),
+            Error::<Test>::InvalidSigner
+        );
+        assert_noop!(
+            Miner::withdraw_balance(Origin::signed(owner), 1, 1_000),
+            Error::<Test>::NoSuchMiner
+        );
+    });
+}
+
+#[test]
+fn withdraw_balance_rejects_outstanding_fee_debt() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        assert_ok!(create_miner_for(owner));
+        pallet_miner::MinerStates::<Test>::mutate(FIRST_MINER_ADDR, |state| {
+            state.as_mut().unwrap().fee_debt = 1
+        });
+
+        assert_noop!(
+            Miner::withdraw_balance(Origin::signed(owner), FIRST_MINER_ADDR, 1_000),
+            Error::<Test>::FeeDebtOutstanding
+        );
+    });
+}
+
+// Creates a miner for `owner` with sector 1 whose owner withdraws every available fund, the
+// sector is then faulty for `MaxFaultDuration` and terminated with a penalty of 10_000 while
+// only 6_000 of its pledge remains after two fault fees
+fn create_miner_with_fee_debt(owner: <Test as frame_system::Config>::AccountId) {
+    create_miner_with_sector(owner, 1);
+    assert_ok!(Miner::withdraw_balance(
+        Origin::signed(owner),
+        FIRST_MINER_ADDR,
+        MINER_BALANCE
+    ));
+    let deadline = Miner::sectors(FIRST_MINER_ADDR, 1).unwrap().deadline;
+    run_to_deadline_open(deadline);
+    let faulted_at = Miner::current_deadline(&FIRST_MINER_ADDR).unwrap().close;
+    run_to_block(faulted_at + 60);
+    assert!(Miner::sectors(FIRST_MINER_ADDR, 1).is_none());
+}
+
+#[test]
+fn penalties_not_covered_by_balance_are_recorded_as_debt() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_fee_debt(owner);
+
+        assert_eq!(
+            Miner::miner_states(FIRST_MINER_ADDR).unwrap().fee_debt,
+            4_000
+        );
+        assert_eq!(Balances::reserved_balance(FIRST_MINER_ADDR), 0);
+        assert!(System::events().iter().any(|record| record.event
+            == Event::pallet_miner(pallet_miner::Event::DebtIncurred(FIRST_MINER_ADDR, 4_000))));
+    });
+}
+
+#[test]
+fn fee_debt_blocks_pre_commits_and_withdrawals() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_fee_debt(owner);
+        let _ = Balances::deposit_creating(&FIRST_MINER_ADDR, 100_000);
+
+        assert_noop!(
+            Miner::pre_commit_sector(
+                Origin::signed(WORKER),
+                FIRST_MINER_ADDR,
+                pre_commit_info(2, System::block_number() - 1)
+            ),
+            Error::<Test>::FeeDebtOutstanding
+        );
+        assert_noop!(
+            Miner::withdraw_balance(Origin::signed(owner), FIRST_MINER_ADDR, 1_000),
+            Error::<Test>::FeeDebtOutstanding
+        );
+    });
+}
+
+#[test]
+fn repay_debt_pays_debt_from_free_balance() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_fee_debt(owner);
+        let treasury_balance = Balances::free_balance(TREASURY);
+
+        // Partially repaid with the funds available
+        let _ = Balances::deposit_creating(&FIRST_MINER_ADDR, 1_000);
+        assert_ok!(Miner::repay_debt(Origin::signed(owner), FIRST_MINER_ADDR));
+        assert_eq!(
+            Miner::miner_states(FIRST_MINER_ADDR).unwrap().fee_debt,
+            3_000
+        );
+        assert_eq!(
+            System::events().pop().map(|e| e.event),
+            Some(Event::pallet_miner(pallet_miner::Event::DebtRepaid(
+                FIRST_MINER_ADDR,
+                1_000
+            )))
+        );
+
+        let _ = Balances::deposit_creating(&FIRST_MINER_ADDR, 5_000);
+        assert_ok!(Miner::repay_debt(Origin::signed(WORKER), FIRST_MINER_ADDR));
+        assert_eq!(Miner::miner_states(FIRST_MINER_ADDR).unwrap().fee_debt, 0);
+        assert_eq!(Balances::free_balance(FIRST_MINER_ADDR), 2_000);
+        assert_eq!(Balances::free_balance(TREASURY), treasury_balance + 4_000);
+
+        // Nothing is left to repay
+        assert_noop!(
+            Miner::repay_debt(Origin::signed(owner), FIRST_MINER_ADDR),
+            Error::<Test>::NoDebt
+        );
+        assert_noop!(
+            Miner::repay_debt(Origin::signed(2), FIRST_MINER_ADDR),
+            Error::<Test>::InvalidSigner
+        );
+    });
+}
+
+#[test]
+fn add_locked_funds_schedules_vesting_steps() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        assert_ok!(create_miner_for(owner));
+
+        assert_ok!(Miner::add_locked_funds(&FIRST_MINER_ADDR, 1_002));
+        assert_eq!(
+            Miner::vesting_funds(FIRST_MINER_ADDR),
+            vec![
+                VestingFund {
+                    epoch: 10,
+                    amount: 250
+                },
+                VestingFund {
+                    epoch: 20,
+                    amount: 251
+                },
+                VestingFund {
+                    epoch: 30,
+                    amount: 250
+                },
+                VestingFund {
+                    epoch: 40,
+                    amount: 251
+                },
+            ]
+        );
+        assert_eq!(
+            Miner::miner_states(FIRST_MINER_ADDR).unwrap().locked_funds,
+            1_002
+        );
+        assert_eq!(Balances::reserved_balance(FIRST_MINER_ADDR), 1_002);
+
+        // Funds vesting at the same block are merged
+        System::set_block_number(10);
+        assert_ok!(Miner::add_locked_funds(&FIRST_MINER_ADDR, 1_000));
+        let schedule: Vec<(u64, u64)> = Miner::vesting_funds(FIRST_MINER_ADDR)
+            .iter()
+            .map(|fund| (fund.epoch, fund.amount))
+            .collect();
+        assert_eq!(
+            schedule,
+            vec![(10, 250), (20, 501), (30, 500), (40, 501), (50, 250)]
+        );
+
+        assert_noop!(
+            Miner::add_locked_funds(&2, 1_000),
+            Error::<Test>::NoSuchMiner
+        );
+    });
+}
+
+#[test]
+fn vested_funds_are_unlocked_lazily_on_miner_calls() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        assert_ok!(create_miner_for(owner));
+        assert_ok!(Miner::add_locked_funds(&FIRST_MINER_ADDR, 1_000));
+        assert_eq!(Miner::vesting_summary(&FIRST_MINER_ADDR), (1_000, 0));
+
+        // Without running the cron the vested funds stay reserved until the miner interacts
+        System::set_block_number(25);
+        assert_eq!(Miner::vesting_summary(&FIRST_MINER_ADDR), (500, 500));
+        assert_eq!(Balances::reserved_balance(FIRST_MINER_ADDR), 1_000);
+
+        // Rejected calls do not unlock them
+        assert_noop!(
+            Miner::extend_sector_expiration(Origin::signed(WORKER), FIRST_MINER_ADDR, 1, 100),
+            Error::<Test>::NoSuchSector
+        );
+        assert_eq!(Balances::reserved_balance(FIRST_MINER_ADDR), 1_000);
+
+        assert_ok!(Miner::withdraw_balance(
+            Origin::signed(owner),
+            FIRST_MINER_ADDR,
+            MINER_BALANCE
+        ));
+        assert_eq!(Balances::free_balance(owner), MINER_BALANCE - 500);
+        assert_eq!(Balances::reserved_balance(FIRST_MINER_ADDR), 500);
+        assert_eq!(
+            Miner::miner_states(FIRST_MINER_ADDR).unwrap().locked_funds,
+            500
+        );
+        assert_eq!(Miner::vesting_summary(&FIRST_MINER_ADDR), (500, 0));
+    });
+}
+
+#[test]
+fn vested_funds_are_unlocked_at_deadline_ends() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        assert_ok!(create_miner_for(owner));
+        assert_ok!(Miner::add_locked_funds(&FIRST_MINER_ADDR, 1_000));
+
+        run_to_block(21);
+        assert_eq!(
+            Miner::miner_states(FIRST_MINER_ADDR).unwrap().locked_funds,
+            500
+        );
+        assert_eq!(Balances::reserved_balance(FIRST_MINER_ADDR), 500);
+        assert_eq!(Miner::vesting_funds(FIRST_MINER_ADDR).len(), 2);
+
+        run_to_block(41);
+        assert_eq!(
+            Miner::miner_states(FIRST_MINER_ADDR).unwrap().locked_funds,
+            0
+        );
+        assert_eq!(Balances::reserved_balance(FIRST_MINER_ADDR), 0);
+        assert!(Miner::vesting_funds(FIRST_MINER_ADDR).is_empty());
+        assert_eq!(Miner::vesting_summary(&FIRST_MINER_ADDR), (0, 0));
+    });
+}
+
+#[test]
+fn distributed_rewards_are_locked() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        assert_ok!(create_miner_for(owner));
+
+        let _ = Balances::deposit_creating(&FIRST_MINER_ADDR, 1_000);
+        <Miner as RewardDistributor>::distribute_reward(&FIRST_MINER_ADDR, 1_000);
+        assert_eq!(Miner::vesting_summary(&FIRST_MINER_ADDR), (1_000, 0));
+        assert_eq!(Balances::free_balance(FIRST_MINER_ADDR), MINER_BALANCE);
+        assert_eq!(Balances::reserved_balance(FIRST_MINER_ADDR), 1_000);
+    });
+}
+
+#[test]
+fn miner_accounts_exposes_owner_and_controlling_addresses() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        assert_ok!(create_miner_for(owner));
+
+        assert_eq!(
+            <Miner as MinerAccounts>::owner(&FIRST_MINER_ADDR),
+            Some(owner)
+        );
+        assert_eq!(<Miner as MinerAccounts>::owner(&owner), None);
+        assert!(Miner::is_controlling_address(&FIRST_MINER_ADDR, &owner));
+        assert!(Miner::is_controlling_address(&FIRST_MINER_ADDR, &WORKER));
+        assert!(!Miner::is_controlling_address(&FIRST_MINER_ADDR, &2));
+        assert!(!Miner::is_controlling_address(&2, &owner));
+    });
+}
+
+#[test]
+fn miner_accounts_exposes_worker() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        assert_ok!(create_miner_for(owner));
+
+        assert_eq!(Miner::worker(&FIRST_MINER_ADDR), Some(WORKER));
+        assert_eq!(Miner::worker(&owner), None);
+    });
+}
+
+#[test]
+fn winning_post_challenges_one_active_sector() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector(owner, 1);
+        add_sector(2);
+
+        let mut challenged = BTreeSet::new();
+        for randomness in 0..20u8 {
+            let sectors = Miner::challenged_sectors(&FIRST_MINER_ADDR, &[randomness]);
+            assert_eq!(sectors.len(), 1);
+            assert_eq!(sectors[0].seal_proof, RegisteredSealProof::StackedDrg2KiBV1);
+            challenged.insert(sectors[0].sector_number);
+        }
+        assert_eq!(challenged, vec![1, 2].into_iter().collect::<BTreeSet<_>>());
+
+        // Faulty sectors are not challenged
+        let deadline = Miner::sectors(FIRST_MINER_ADDR, 1).unwrap().deadline;
+        assert_ok!(Miner::declare_faults(
+            Origin::signed(owner),
+            FIRST_MINER_ADDR,
+            vec![fault_declaration(deadline, vec![1])]
+        ));
+        for randomness in 0..20u8 {
+            let sectors = Miner::challenged_sectors(&FIRST_MINER_ADDR, &[randomness]);
+            assert_eq!(sectors.len(), 1);
+            assert_eq!(sectors[0].sector_number, 2);
+        }
+
+        assert!(Miner::challenged_sectors(&owner, &[0]).is_empty());
+    });
+}
+
+const REPORTER: u64 = 77;
+
+#[test]
+fn report_consensus_fault_slashes_pledge_and_rewards_reporter() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector(owner, 1);
+        System::set_block_number(25);
+        assert!(Power::is_eligible(&FIRST_MINER_ADDR));
+
+        assert_ok!(Miner::report_consensus_fault(
+            Origin::signed(REPORTER),
+            FIRST_MINER_ADDR,
+            block_header(WORKER, 22, 0),
+            block_header(WORKER, 22, 1),
+            Vec::new()
+        ));
+        // The penalty of 5 block rewards is slashed from the pledge of 10_000, a quarter of it
+        // is paid to the reporter
+        assert_eq!(
+            Miner::miner_states(FIRST_MINER_ADDR)
+                .unwrap()
+                .initial_pledge,
+            5_000
+        );
+        assert_eq!(Balances::reserved_balance(FIRST_MINER_ADDR), 5_000);
+        assert_eq!(Balances::free_balance(REPORTER), 1_250);
+        assert_eq!(Balances::free_balance(TREASURY), 3_750);
+        assert_eq!(
+            System::events().pop().map(|e| e.event),
+            Some(Event::pallet_miner(
+                pallet_miner::Event::ConsensusFaultReported(
+                    FIRST_MINER_ADDR,
+                    REPORTER,
+                    5_000,
+                    1_250
+                )
+            ))
+        );
+
+        // The miner is ineligible for 30 blocks and the fault can not be reported again
+        assert!(!Power::is_eligible(&FIRST_MINER_ADDR));
+        assert_noop!(
+            Miner::report_consensus_fault(
+                Origin::signed(REPORTER),
+                FIRST_MINER_ADDR,
+                block_header(WORKER, 22, 0),
+                block_header(WORKER, 22, 1),
+                Vec::new()
+            ),
+            Error::<Test>::ConsensusFaultTooOld
+        );
+        System::set_block_number(55);
+        assert!(Power::is_eligible(&FIRST_MINER_ADDR));
+    });
+}
+
+#[test]
+fn report_consensus_fault_slashes_free_balance_without_pledge() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        assert_ok!(create_miner_for(owner));
+        System::set_block_number(10);
+
+        assert_ok!(Miner::report_consensus_fault(
+            Origin::signed(REPORTER),
+            FIRST_MINER_ADDR,
+            block_header(WORKER, 5, 0),
+            block_header(WORKER, 5, 1),
+            Vec::new()
+        ));
+        assert_eq!(
+            Balances::free_balance(FIRST_MINER_ADDR),
+            MINER_BALANCE - 5_000
+        );
+        assert_eq!(Balances::free_balance(REPORTER), 1_250);
+        assert_eq!(Balances::free_balance(TREASURY), 3_750);
+    });
+}
+
+#[test]
+fn report_consensus_fault_reports_the_amounts_actually_slashed_and_paid() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        assert_ok!(create_miner_for(owner));
+        Balances::make_free_balance_be(&FIRST_MINER_ADDR, 1_000);
+        System::set_block_number(10);
+
+        // Only 1_000 of the penalty of 5_000 can be slashed, the reward is capped at it
+        assert_ok!(Miner::report_consensus_fault(
+            Origin::signed(REPORTER),
+            FIRST_MINER_ADDR,
+            block_header(WORKER, 5, 0),
+            block_header(WORKER, 5, 1),
+            Vec::new()
+        ));
+        assert_eq!(Balances::free_balance(REPORTER), 1_000);
+        assert_eq!(Balances::free_balance(TREASURY), 0);
+        assert_eq!(
+            Miner::miner_states(FIRST_MINER_ADDR).unwrap().fee_debt,
+            4_000
+        );
+        assert_eq!(
+            System::events().pop().map(|e| e.event),
+            Some(Event::pallet_miner(
+                pallet_miner::Event::ConsensusFaultReported(
+                    FIRST_MINER_ADDR,
+                    REPORTER,
+                    1_000,
+                    1_000
+                )
+            ))
+        );
+    });
+}
+
+#[test]
+fn report_consensus_fault_rejects_invalid_evidence() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        assert_ok!(create_miner_for(owner));
+        System::set_block_number(10);
+
+        assert_noop!(
+            Miner::report_consensus_fault(
+                Origin::signed(REPORTER),
+                owner,
+                block_header(WORKER, 5, 0),
+                block_header(WORKER, 5, 1),
+                Vec::new()
+            ),
+            Error::<Test>::NoSuchMiner
+        );
+        // Headers signed by another worker
+        assert_noop!(
+            Miner::report_consensus_fault(
+                Origin::signed(REPORTER),
+                FIRST_MINER_ADDR,
+                block_header(owner, 5, 0),
+                block_header(owner, 5, 1),
+                Vec::new()
+            ),
+            Error::<Test>::InvalidConsensusFault
+        );
+        // Identical headers
+        assert_noop!(
+            Miner::report_consensus_fault(
+                Origin::signed(REPORTER),
+                FIRST_MINER_ADDR,
+                block_header(WORKER, 5, 0),
+                block_header(WORKER, 5, 0),
+                Vec::new()
+            ),
+            Error::<Test>::InvalidConsensusFault
+        );
+        // Faults of the current block
+        assert_noop!(
+            Miner::report_consensus_fault(
+                Origin::signed(REPORTER),
+                FIRST_MINER_ADDR,
+                block_header(WORKER, 10, 0),
+                block_header(WORKER, 10, 1),
+                Vec::new()
+            ),
+            Error::<Test>::InvalidConsensusFault
+        );
+        assert_eq!(Balances::free_balance(FIRST_MINER_ADDR), MINER_BALANCE);
+    });
+}
+
+#[test]
+fn report_consensus_fault_rejects_stale_evidence() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        assert_ok!(create_miner_for(owner));
+        System::set_block_number(30);
+
+        // Faults older than 20 blocks can no longer be reported
+        assert_noop!(
+            Miner::report_consensus_fault(
+                Origin::signed(REPORTER),
+                FIRST_MINER_ADDR,
+                block_header(WORKER, 9, 0),
+                block_header(WORKER, 9, 1),
+                Vec::new()
+            ),
+            Error::<Test>::ConsensusFaultTooOld
+        );
+        assert_ok!(Miner::report_consensus_fault(
+            Origin::signed(REPORTER),
+            FIRST_MINER_ADDR,
+            block_header(WORKER, 10, 0),
+            block_header(WORKER, 10, 1),
+            Vec::new()
+        ));
+    });
+}
+
+#[test]
+fn remove_miner_transfers_balance_and_deregisters_miner() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_sector(owner, 1);
+        let deadline = Miner::sectors(FIRST_MINER_ADDR, 1).unwrap().deadline;
+        run_to_block(31);
+        assert_ok!(Miner::terminate_sectors(
+            Origin::signed(owner),
+            FIRST_MINER_ADDR,
+            vec![fault_declaration(deadline, vec![1])]
+        ));
+        assert_eq!(Power::miner_count(), Some(1));
+
+        assert_ok!(Miner::remove_miner(Origin::signed(owner), FIRST_MINER_ADDR));
+        assert_eq!(Balances::free_balance(owner), MINER_BALANCE - 5_000);
+        assert_eq!(Balances::total_balance(&FIRST_MINER_ADDR), 0);
+        assert!(Miner::miners(FIRST_MINER_ADDR).is_none());
+        assert!(Miner::miner_states(FIRST_MINER_ADDR).is_none());
+        assert!(Miner::deadlines(FIRST_MINER_ADDR, deadline)
+            .partitions
+            .is_empty());
+        assert_eq!(Power::claims(FIRST_MINER_ADDR), None);
+        assert_eq!(Power::miner_count(), Some(0));
+        assert_eq!(
+            System::events().pop().map(|e| e.event),
+            Some(Event::pallet_miner(pallet_miner::Event::MinerRemoved(
+                FIRST_MINER_ADDR,
+                owner,
+                MINER_BALANCE - 5_000
+            )))
+        );
+
+        // Deadlines of the removed miner are no longer processed
+        run_to_block(200);
+        assert!(Miner::miner_states(FIRST_MINER_ADDR).is_none());
+        assert_noop!(
+            Miner::remove_miner(Origin::signed(owner), FIRST_MINER_ADDR),
+            Error::<Test>::NoSuchMiner
+        );
+    });
+}
+
+#[test]
+fn remove_miner_rejects_invalid_signer_and_live_miners() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        create_miner_with_pre_commit(owner, 1);
+
+        assert_noop!(
+            Miner::remove_miner(Origin::signed(WORKER), FIRST_MINER_ADDR),
+            Error::<Test>::InvalidSigner
+        );
+        assert_noop!(
+            Miner::remove_miner(Origin::signed(owner), FIRST_MINER_ADDR),
+            Error::<Test>::MinerHasPreCommits
+        );
+
+        run_to_block(21);
+        assert_ok!(Miner::prove_commit_sector(
+            Origin::signed(WORKER),
+            FIRST_MINER_ADDR,
+            1,
+            seal_proof(1)
+        ));
+        assert_noop!(
+            Miner::remove_miner(Origin::signed(owner), FIRST_MINER_ADDR),
+            Error::<Test>::MinerHasSectors
+        );
+    });
+}
+
+#[test]
+fn remove_miner_rejects_market_escrow() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        assert_ok!(create_miner_for(owner));
+        add_escrow(FIRST_MINER_ADDR);
+
+        // The escrow must be withdrawn from the market first
+        assert_noop!(
+            Miner::remove_miner(Origin::signed(owner), FIRST_MINER_ADDR),
+            Error::<Test>::MinerHasMarketEscrow
+        );
+        assert_eq!(Power::miner_count(), Some(1));
+    });
+}
+
+#[test]
+fn remove_miner_rejects_locked_funds() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        assert_ok!(create_miner_for(owner));
+        System::set_block_number(1);
+        Miner::distribute_reward(&FIRST_MINER_ADDR, 1_000);
+
+        assert_noop!(
+            Miner::remove_miner(Origin::signed(owner), FIRST_MINER_ADDR),
+            Error::<Test>::MinerHasLockedFunds
+        );
+        // Partly vested funds are not unlocked by a rejected removal
+        System::set_block_number(15);
+        assert_noop!(
+            Miner::remove_miner(Origin::signed(owner), FIRST_MINER_ADDR),
+            Error::<Test>::MinerHasLockedFunds
+        );
+        assert_eq!(Power::miner_count(), Some(1));
+
+        // Funds vested by the removal are transferred to the owner
+        System::set_block_number(41);
+        assert_ok!(Miner::remove_miner(Origin::signed(owner), FIRST_MINER_ADDR));
+        assert_eq!(Balances::free_balance(owner), MINER_BALANCE);
+        assert_eq!(Power::miner_count(), Some(0));
+    });
+}
+
+#[test]
+fn remove_miner_keeps_miner_when_transfer_fails() {
+    new_test_ext().execute_with(|| {
+        let owner: u64 = 123;
+        assert_ok!(create_miner_for(owner));
+        System::set_block_number(1);
+        Miner::distribute_reward(&FIRST_MINER_ADDR, 1_000);
+        System::set_block_number(41);
+        Balances::set_lock(*b"testlock", &FIRST_MINER_ADDR, 1, WithdrawReasons::all());
+
+        // Nothing is written, the vested funds are still locked and the miner is registered
+        assert_noop!(
+            Miner::remove_miner(Origin::signed(owner), FIRST_MINER_ADDR),
+            pallet_balances::Error::<Test>::LiquidityRestrictions
+        );
+        assert_eq!(Power::claims(FIRST_MINER_ADDR), Some(Claim::default()));
+        assert_eq!(Power::miner_count(), Some(1));
+        assert!(Miner::miners(FIRST_MINER_ADDR).is_some());
+        assert_eq!(Miner::vesting_funds(FIRST_MINER_ADDR).len(), 4);
+
+        Balances::remove_lock(*b"testlock", &FIRST_MINER_ADDR);
+        assert_ok!(Miner::remove_miner(Origin::signed(owner), FIRST_MINER_ADDR));
+        assert_eq!(Power::claims(FIRST_MINER_ADDR), None);
+    });
+}
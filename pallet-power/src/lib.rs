@@ -6,23 +6,39 @@ mod tests;
 #[cfg(test)]
 mod mock;
 
-use pallet_common::{Claim, Power};
+use pallet_common::{Claim, Power, PowerDelta, RegisteredSealProof};
+use sp_runtime::traits::{Convert, Saturating, Zero};
+use sp_std::vec::Vec;
 
 // `pallet::Module` is created by `pallet` macro
-pub use pallet::{Claims, Config, MinerCount, Module, Pallet, TotalRawBytesPower};
+pub use pallet::{
+    ClaimHistory, Claims, Config, Event, MinerAboveMinPowerCount, MinerAboveMinPowerHistory,
+    MinerCount, Module, Pallet, PowerHistory, SealProofTypes, SuspendedUntil,
+    TotalQualityAdjustedPower, TotalRawBytesPower,
+};
 
 #[frame_support::pallet]
 pub mod pallet {
     use super::*;
     use frame_support::pallet_prelude::*;
     use frame_system::pallet_prelude::*;
+    use sp_runtime::traits::AtLeast32BitUnsigned;
 
     #[pallet::config]
     pub trait Config: frame_system::Config {
+        type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
         /// Libp2p Peer Identifier, usually array of bytes
         type PeerId: Parameter + Member + AsRef<[u8]> + Clone + Send + 'static;
         /// Unit used for recoding raw bytes and quality adjusted power
-        type StoragePower: Parameter + Member + Clone + Default;
+        type StoragePower: Parameter + Member + AtLeast32BitUnsigned + Copy + Default;
+        /// Minimum raw bytes power for a miner sealing with the given proof type to be eligible
+        /// to produce blocks
+        type ConsensusMinerMinPower: Convert<RegisteredSealProof, Self::StoragePower>;
+        /// Number of miners meeting the consensus minimum power below which any miner with power
+        /// is eligible to produce blocks
+        type ConsensusMinerMinMiners: Get<u64>;
+        /// Number of blocks the changes of claims and of the total power are retained for
+        type PowerHistoryRetention: Get<Self::BlockNumber>;
     }
 
     #[pallet::pallet]
@@ -43,19 +59,137 @@ pub mod pallet {
     #[pallet::getter(fn miner_count)]
     pub type MinerCount<T: Config> = StorageValue<_, u64>;
 
+    /// Seal proof type of the miners, their consensus minimum power depends on it
+    #[pallet::storage]
+    #[pallet::getter(fn seal_proof_types)]
+    pub type SealProofTypes<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, RegisteredSealProof>;
+
+    /// Total Miner with raw bytes power meeting the consensus minimum power
+    #[pallet::storage]
+    #[pallet::getter(fn miner_above_min_power_count)]
+    pub type MinerAboveMinPowerCount<T: Config> = StorageValue<_, u64>;
+
+    /// Block before which a miner is ineligible to produce blocks, e.g. after a consensus fault
+    #[pallet::storage]
+    #[pallet::getter(fn suspended_until)]
+    pub type SuspendedUntil<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, T::BlockNumber>;
+
+    /// Claims of miners at the end of the blocks they changed in, within the retention window
+    /// and the latest change before it
+    #[pallet::storage]
+    #[pallet::getter(fn claim_history)]
+    pub type ClaimHistory<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        Vec<(T::BlockNumber, Claim<T::StoragePower>)>,
+        ValueQuery,
+    >;
+
+    /// Total power of the system at the end of the blocks it changed in, within the retention
+    /// window and the latest change before it
+    #[pallet::storage]
+    #[pallet::getter(fn power_history)]
+    pub type PowerHistory<T: Config> =
+        StorageValue<_, Vec<(T::BlockNumber, Claim<T::StoragePower>)>, ValueQuery>;
+
+    /// Total Miner with raw bytes power meeting the consensus minimum power at the end of the
+    /// blocks it changed in, within the retention window and the latest change before it
+    #[pallet::storage]
+    #[pallet::getter(fn miner_above_min_power_history)]
+    pub type MinerAboveMinPowerHistory<T: Config> =
+        StorageValue<_, Vec<(T::BlockNumber, u64)>, ValueQuery>;
+
     /// Total Power in Raw bytes declared in the system
     #[pallet::storage]
     #[pallet::getter(fn total_raw_bytes_power)]
-    pub type TotalRawBytesPower<T: Config> = StorageValue<_, u64>;
+    pub type TotalRawBytesPower<T: Config> = StorageValue<_, T::StoragePower>;
+
+    /// Total Quality Adjusted Power declared in the system
+    #[pallet::storage]
+    #[pallet::getter(fn total_quality_adjusted_power)]
+    pub type TotalQualityAdjustedPower<T: Config> = StorageValue<_, T::StoragePower>;
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    #[pallet::metadata(T::AccountId = "AccountID")]
+    pub enum Event<T: Config> {
+        /// Emits miner address
+        MinerDeregistered(T::AccountId),
+    }
 
     #[pallet::call]
-    impl<T: Config> Pallet<T> {
-        /// This is a placeholder
-        /// `frame_support::pallet` macro require pallet::call but no call in this phase
-        #[pallet::weight(10_000)]
-        fn do_something(_: OriginFor<T>) -> DispatchResultWithPostInfo {
-            unimplemented!()
+    impl<T: Config> Pallet<T> {}
+}
+
+impl<T: Config> Pallet<T> {
+    /// Records `value` as of the end of the current block in `history` and prunes the changes
+    /// falling out of the retention window, the latest of them is kept as the value at the start
+    /// of the window
+    fn record_change<V>(history: &mut Vec<(T::BlockNumber, V)>, value: V) {
+        let now = <frame_system::Module<T>>::block_number();
+        match history.last_mut() {
+            Some((changed, last_value)) if *changed == now => *last_value = value,
+            _ => history.push((now, value)),
+        }
+        let window_start = now.saturating_sub(T::PowerHistoryRetention::get());
+        let expired = history
+            .iter()
+            .take_while(|(changed, _)| *changed < window_start)
+            .count();
+        if expired > 1 {
+            history.drain(..expired - 1);
+        }
+    }
+
+    /// Whether the claims at the start of `block` are retained, future blocks are not
+    fn is_retained(block: T::BlockNumber) -> bool {
+        let now = <frame_system::Module<T>>::block_number();
+        block <= now && block >= now.saturating_sub(T::PowerHistoryRetention::get())
+    }
+
+    /// Value at the start of `block`, the latest change in `history` before it
+    fn value_before<V: Clone>(history: &[(T::BlockNumber, V)], block: T::BlockNumber) -> Option<V> {
+        history
+            .iter()
+            .rev()
+            .find(|(changed, _)| *changed < block)
+            .map(|(_, value)| value.clone())
+    }
+
+    /// Whether `miner` sealing with `seal_proof` and claiming `raw_bytes_power` while
+    /// `miner_above_min_power_count` miners meet the consensus minimum power is eligible to
+    /// produce blocks at the current block
+    fn is_eligible_with(
+        miner: &T::AccountId,
+        seal_proof: RegisteredSealProof,
+        raw_bytes_power: T::StoragePower,
+        miner_above_min_power_count: u64,
+    ) -> bool {
+        // following MinerNominalPowerMeetsConsensusMinimum in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/power/power_state.go
+        // following ConsensusFaultElapsed of miner info, suspended miners are not eligible
+        if SuspendedUntil::<T>::get(miner).map_or(false, |until| {
+            <frame_system::Module<T>>::block_number() < until
+        }) {
+            return false;
+        }
+        if Self::meets_consensus_minimum(raw_bytes_power, seal_proof) {
+            return true;
         }
+        // Until enough miners meet the minimum, any miner with power can produce blocks
+        miner_above_min_power_count < T::ConsensusMinerMinMiners::get()
+            && !raw_bytes_power.is_zero()
+    }
+
+    /// Whether `raw_bytes_power` meets the consensus minimum power of `seal_proof`
+    fn meets_consensus_minimum(
+        raw_bytes_power: T::StoragePower,
+        seal_proof: RegisteredSealProof,
+    ) -> bool {
+        !raw_bytes_power.is_zero()
+            && raw_bytes_power >= T::ConsensusMinerMinPower::convert(seal_proof)
     }
 }
 
@@ -63,8 +197,12 @@ impl<T: Config> Power for Pallet<T> {
     type AccountId = T::AccountId;
     type StoragePower = T::StoragePower;
     type PeerId = T::PeerId;
+    type BlockNumber = T::BlockNumber;
 
-    fn register_new_miner(miner: &T::AccountId) -> Option<Claim<Self::StoragePower>> {
+    fn register_new_miner(
+        miner: &T::AccountId,
+        seal_proof: RegisteredSealProof,
+    ) -> Option<Claim<Self::StoragePower>> {
         // following https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/power/power_actor.go#L103
         // Note: Instead of external transactions to the power actor and instantiating a miner actor,
         // this is called by the `Miner::create` method
@@ -72,6 +210,8 @@ impl<T: Config> Power for Pallet<T> {
         if let Some(new_miner_count) = miner_count.checked_add(1) {
             let claim = Claim::default();
             Claims::<T>::insert(miner, claim.clone());
+            ClaimHistory::<T>::mutate(miner, |history| Self::record_change(history, claim.clone()));
+            SealProofTypes::<T>::insert(miner, seal_proof);
             MinerCount::<T>::put(new_miner_count);
             Some(claim)
         } else {
@@ -79,12 +219,145 @@ impl<T: Config> Power for Pallet<T> {
         }
     }
 
+    fn deregister_miner(miner: &T::AccountId) -> Option<Claim<Self::StoragePower>> {
+        // following deleteMinerActor in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/power/power_actor.go
+        // Note: The history of the claim is removed too, the miner has no claim at past blocks
+        let claim = Claims::<T>::get(miner)?;
+        if !claim.raw_bytes_power.is_zero() || !claim.quality_adjusted_power.is_zero() {
+            return None;
+        }
+        Claims::<T>::remove(miner);
+        ClaimHistory::<T>::remove(miner);
+        SealProofTypes::<T>::remove(miner);
+        SuspendedUntil::<T>::remove(miner);
+        MinerCount::<T>::mutate(|miner_count| {
+            *miner_count = Some(miner_count.unwrap_or_default().saturating_sub(1));
+        });
+        Self::deposit_event(Event::MinerDeregistered(miner.clone()));
+        Some(claim)
+    }
+
     fn update_claim(
-        _miner: <T as frame_system::Config>::AccountId,
-        _raw_bytes_delta: Self::StoragePower,
-        _quality_adjusted_delta: Self::StoragePower,
+        miner: <T as frame_system::Config>::AccountId,
+        raw_bytes_delta: PowerDelta<Self::StoragePower>,
+        quality_adjusted_delta: PowerDelta<Self::StoragePower>,
     ) -> Option<Claim<Self::StoragePower>> {
         // following https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/power/power_actor.go#L161
-        unimplemented!()
+        // Note: totals are only written once every delta has been applied successfully, so a
+        // failed update leaves both the claim and the totals untouched. Totals include the power
+        // of miners below the consensus minimum power
+        Claims::<T>::try_mutate(
+            &miner,
+            |maybe_claim| -> Result<Claim<T::StoragePower>, ()> {
+                let claim = maybe_claim.as_mut().ok_or(())?;
+                let seal_proof = SealProofTypes::<T>::get(&miner).ok_or(())?;
+
+                let raw_bytes_power = raw_bytes_delta.apply(claim.raw_bytes_power).ok_or(())?;
+                let quality_adjusted_power = quality_adjusted_delta
+                    .apply(claim.quality_adjusted_power)
+                    .ok_or(())?;
+                let total_raw_bytes_power = raw_bytes_delta
+                    .apply(TotalRawBytesPower::<T>::get().unwrap_or_default())
+                    .ok_or(())?;
+                let total_quality_adjusted_power = quality_adjusted_delta
+                    .apply(TotalQualityAdjustedPower::<T>::get().unwrap_or_default())
+                    .ok_or(())?;
+
+                let was_above_min_power =
+                    Self::meets_consensus_minimum(claim.raw_bytes_power, seal_proof);
+                let is_above_min_power = Self::meets_consensus_minimum(raw_bytes_power, seal_proof);
+                if was_above_min_power != is_above_min_power {
+                    let count = MinerAboveMinPowerCount::<T>::get().unwrap_or_default();
+                    let count = if is_above_min_power {
+                        count.saturating_add(1)
+                    } else {
+                        count.saturating_sub(1)
+                    };
+                    MinerAboveMinPowerCount::<T>::put(count);
+                    MinerAboveMinPowerHistory::<T>::mutate(|history| {
+                        Self::record_change(history, count)
+                    });
+                }
+
+                claim.raw_bytes_power = raw_bytes_power;
+                claim.quality_adjusted_power = quality_adjusted_power;
+                TotalRawBytesPower::<T>::put(total_raw_bytes_power);
+                TotalQualityAdjustedPower::<T>::put(total_quality_adjusted_power);
+                ClaimHistory::<T>::mutate(&miner, |history| {
+                    Self::record_change(history, claim.clone())
+                });
+                PowerHistory::<T>::mutate(|history| {
+                    Self::record_change(
+                        history,
+                        Claim {
+                            raw_bytes_power: total_raw_bytes_power,
+                            quality_adjusted_power: total_quality_adjusted_power,
+                        },
+                    )
+                });
+
+                Ok(claim.clone())
+            },
+        )
+        .ok()
+    }
+
+    fn total_power() -> Claim<Self::StoragePower> {
+        Claim {
+            raw_bytes_power: TotalRawBytesPower::<T>::get().unwrap_or_default(),
+            quality_adjusted_power: TotalQualityAdjustedPower::<T>::get().unwrap_or_default(),
+        }
+    }
+
+    fn claim(miner: &T::AccountId) -> Option<Claim<Self::StoragePower>> {
+        Claims::<T>::get(miner)
+    }
+
+    fn is_eligible(miner: &T::AccountId) -> bool {
+        match (Claims::<T>::get(miner), SealProofTypes::<T>::get(miner)) {
+            (Some(claim), Some(seal_proof)) => Self::is_eligible_with(
+                miner,
+                seal_proof,
+                claim.raw_bytes_power,
+                MinerAboveMinPowerCount::<T>::get().unwrap_or_default(),
+            ),
+            _ => false,
+        }
+    }
+
+    fn is_eligible_at(miner: &T::AccountId, block: T::BlockNumber) -> bool {
+        match (
+            Self::claim_at(miner, block),
+            SealProofTypes::<T>::get(miner),
+        ) {
+            (Some(claim), Some(seal_proof)) => Self::is_eligible_with(
+                miner,
+                seal_proof,
+                claim.raw_bytes_power,
+                Self::value_before(&MinerAboveMinPowerHistory::<T>::get(), block)
+                    .unwrap_or_default(),
+            ),
+            _ => false,
+        }
+    }
+
+    fn suspend_eligibility(miner: &T::AccountId, until: T::BlockNumber) {
+        SuspendedUntil::<T>::mutate(miner, |suspended_until| {
+            *suspended_until = Some(suspended_until.map_or(until, |current| current.max(until)));
+        });
+    }
+
+    fn claim_at(miner: &T::AccountId, block: T::BlockNumber) -> Option<Claim<Self::StoragePower>> {
+        if !Self::is_retained(block) {
+            return None;
+        }
+        Self::value_before(&ClaimHistory::<T>::get(miner), block)
+    }
+
+    fn total_power_at(block: T::BlockNumber) -> Option<Claim<Self::StoragePower>> {
+        if !Self::is_retained(block) {
+            return None;
+        }
+        Some(Self::value_before(&PowerHistory::<T>::get(), block).unwrap_or_default())
     }
 }
@@ -1,10 +1,11 @@
 use crate as pallet_power;
 use frame_support::parameter_types;
 use frame_system as system;
+use pallet_common::RegisteredSealProof;
 use sp_core::H256;
 use sp_runtime::{
     testing::Header,
-    traits::{BlakeTwo256, IdentityLookup},
+    traits::{BlakeTwo256, Convert, IdentityLookup},
 };
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
@@ -17,7 +18,7 @@ frame_support::construct_runtime!(
         UncheckedExtrinsic = UncheckedExtrinsic,
         {
             System: frame_system::{Module, Call, Config, Storage, Event<T>},
-            Power: pallet_power::{Module, Storage},
+            Power: pallet_power::{Module, Storage, Event<T>},
         }
 );
 
@@ -52,9 +53,27 @@ impl system::Config for Test {
     type SS58Prefix = SS58Prefix;
 }
 
+/// Consensus minimum power of 10 sectors
+pub struct ConsensusMinerMinPower;
+
+impl Convert<RegisteredSealProof, u128> for ConsensusMinerMinPower {
+    fn convert(seal_proof: RegisteredSealProof) -> u128 {
+        seal_proof.sector_size() as u128 * 10
+    }
+}
+
+parameter_types! {
+    pub ConsensusMinerMinMiners: u64 = 2;
+    pub const PowerHistoryRetention: u64 = 30;
+}
+
 impl pallet_power::Config for Test {
+    type Event = Event;
     type PeerId = Vec<u8>;
     type StoragePower = u128;
+    type ConsensusMinerMinPower = ConsensusMinerMinPower;
+    type ConsensusMinerMinMiners = ConsensusMinerMinMiners;
+    type PowerHistoryRetention = PowerHistoryRetention;
 }
 
 // Build genesis storage according to the mock runtime.
@@ -1,5 +1,9 @@
-use crate::mock::{new_test_ext, Power};
-use pallet_common::{Claim, Power as PowerTrait};
+// Imports created by construct_runtime macros are unresolved by rust analyzer
+use crate as pallet_power;
+use crate::mock::{new_test_ext, Event, Power, System};
+use pallet_common::{Claim, Power as PowerTrait, PowerDelta, RegisteredSealProof};
+
+const SEAL_PROOF: RegisteredSealProof = RegisteredSealProof::StackedDrg2KiBV1;
 
 #[test]
 fn register_new_miner() {
@@ -7,10 +11,352 @@ fn register_new_miner() {
         let miner_account: u64 = 1;
         let expected_claim = Claim::<u128>::default();
 
-        Power::register_new_miner(&miner_account).expect("Registration failed");
+        Power::register_new_miner(&miner_account, SEAL_PROOF).expect("Registration failed");
 
         let claim = Power::claims(miner_account);
         assert_eq!(claim.is_some(), true);
         assert_eq!(claim.unwrap(), expected_claim);
+        assert_eq!(Power::seal_proof_types(miner_account), Some(SEAL_PROOF));
+    });
+}
+
+#[test]
+fn update_claim_applies_deltas_and_totals() {
+    new_test_ext().execute_with(|| {
+        let miner_account: u64 = 1;
+        Power::register_new_miner(&miner_account, SEAL_PROOF).expect("Registration failed");
+
+        let claim = Power::update_claim(
+            miner_account,
+            PowerDelta::Increase(100),
+            PowerDelta::Increase(1000),
+        )
+        .expect("Update failed");
+        assert_eq!(claim.raw_bytes_power, 100);
+        assert_eq!(claim.quality_adjusted_power, 1000);
+
+        let claim = Power::update_claim(
+            miner_account,
+            PowerDelta::Decrease(40),
+            PowerDelta::Decrease(400),
+        )
+        .expect("Update failed");
+        assert_eq!(claim.raw_bytes_power, 60);
+        assert_eq!(claim.quality_adjusted_power, 600);
+
+        assert_eq!(Power::claims(miner_account), Some(claim.clone()));
+        assert_eq!(Power::claim(&miner_account), Some(claim));
+        assert_eq!(Power::total_raw_bytes_power(), Some(60));
+        assert_eq!(Power::total_quality_adjusted_power(), Some(600));
+        assert_eq!(
+            Power::total_power(),
+            Claim {
+                raw_bytes_power: 60,
+                quality_adjusted_power: 600,
+            }
+        );
+    });
+}
+
+#[test]
+fn update_claim_rejects_unknown_miner() {
+    new_test_ext().execute_with(|| {
+        let unknown_miner: u64 = 2;
+
+        assert!(Power::update_claim(
+            unknown_miner,
+            PowerDelta::Increase(100),
+            PowerDelta::Increase(100)
+        )
+        .is_none());
+        assert!(Power::claims(unknown_miner).is_none());
+        assert_eq!(Power::total_raw_bytes_power(), None);
+    });
+}
+
+#[test]
+fn update_claim_rejects_underflow_without_side_effects() {
+    new_test_ext().execute_with(|| {
+        let miner_account: u64 = 1;
+        Power::register_new_miner(&miner_account, SEAL_PROOF).expect("Registration failed");
+        Power::update_claim(
+            miner_account,
+            PowerDelta::Increase(100),
+            PowerDelta::Increase(100),
+        )
+        .expect("Update failed");
+
+        // raw bytes delta is valid but quality adjusted delta underflows
+        assert!(Power::update_claim(
+            miner_account,
+            PowerDelta::Decrease(50),
+            PowerDelta::Decrease(101)
+        )
+        .is_none());
+
+        let claim = Power::claims(miner_account).unwrap();
+        assert_eq!(claim.raw_bytes_power, 100);
+        assert_eq!(claim.quality_adjusted_power, 100);
+        assert_eq!(Power::total_raw_bytes_power(), Some(100));
+        assert_eq!(Power::total_quality_adjusted_power(), Some(100));
+    });
+}
+
+#[test]
+fn update_claim_rejects_overflow() {
+    new_test_ext().execute_with(|| {
+        let miner_account: u64 = 1;
+        Power::register_new_miner(&miner_account, SEAL_PROOF).expect("Registration failed");
+        Power::update_claim(
+            miner_account,
+            PowerDelta::Increase(u128::MAX),
+            PowerDelta::Increase(u128::MAX),
+        )
+        .expect("Update failed");
+
+        assert!(Power::update_claim(
+            miner_account,
+            PowerDelta::Increase(1),
+            PowerDelta::Increase(0)
+        )
+        .is_none());
+    });
+}
+
+#[test]
+fn is_eligible_requires_consensus_minimum_power() {
+    new_test_ext().execute_with(|| {
+        let min_power = SEAL_PROOF.sector_size() as u128 * 10;
+        for miner_account in 1..=3 {
+            Power::register_new_miner(&miner_account, SEAL_PROOF).expect("Registration failed");
+            assert!(!Power::is_eligible(&miner_account));
+        }
+
+        // Any miner with power is eligible while fewer than 2 miners meet the minimum
+        Power::update_claim(1, PowerDelta::Increase(100), PowerDelta::Increase(100))
+            .expect("Update failed");
+        assert!(Power::is_eligible(&1));
+        assert_eq!(Power::miner_above_min_power_count(), None);
+
+        for miner_account in 2..=3 {
+            Power::update_claim(
+                miner_account,
+                PowerDelta::Increase(min_power),
+                PowerDelta::Increase(min_power),
+            )
+            .expect("Update failed");
+        }
+        assert_eq!(Power::miner_above_min_power_count(), Some(2));
+        assert!(!Power::is_eligible(&1));
+        assert!(Power::is_eligible(&2));
+        assert!(Power::is_eligible(&3));
+
+        Power::update_claim(3, PowerDelta::Decrease(1), PowerDelta::Decrease(1))
+            .expect("Update failed");
+        assert_eq!(Power::miner_above_min_power_count(), Some(1));
+        assert!(Power::is_eligible(&1));
+        assert!(Power::is_eligible(&3));
+
+        // Unknown miners are never eligible
+        assert!(!Power::is_eligible(&4));
+    });
+}
+
+#[test]
+fn is_eligible_at_uses_power_at_block() {
+    new_test_ext().execute_with(|| {
+        let min_power = SEAL_PROOF.sector_size() as u128 * 10;
+        System::set_block_number(1);
+        for miner_account in 1..=3 {
+            Power::register_new_miner(&miner_account, SEAL_PROOF).expect("Registration failed");
+        }
+        Power::update_claim(1, PowerDelta::Increase(100), PowerDelta::Increase(100))
+            .expect("Update failed");
+
+        System::set_block_number(5);
+        for miner_account in 2..=3 {
+            Power::update_claim(
+                miner_account,
+                PowerDelta::Increase(min_power),
+                PowerDelta::Increase(min_power),
+            )
+            .expect("Update failed");
+        }
+        assert_eq!(Power::miner_above_min_power_history(), vec![(5, 2)]);
+        assert!(!Power::is_eligible(&1));
+        assert!(!Power::is_eligible_at(&2, 5));
+
+        // Before block 5 fewer than 2 miners met the minimum
+        System::set_block_number(10);
+        assert!(Power::is_eligible_at(&1, 5));
+        assert!(!Power::is_eligible_at(&1, 6));
+        assert!(Power::is_eligible_at(&2, 6));
+        // Miners without power, unknown miners and blocks out of the retention window are not
+        // eligible
+        assert!(!Power::is_eligible_at(&1, 1));
+        assert!(!Power::is_eligible_at(&4, 6));
+        System::set_block_number(40);
+        assert!(!Power::is_eligible_at(&2, 9));
+        assert!(Power::is_eligible_at(&2, 10));
+
+        // Suspended miners are not eligible at any block
+        Power::suspend_eligibility(&2, 50);
+        assert!(!Power::is_eligible_at(&2, 10));
+    });
+}
+
+#[test]
+fn claim_history_records_changes_within_retention() {
+    new_test_ext().execute_with(|| {
+        let miner_account: u64 = 1;
+        System::set_block_number(1);
+        Power::register_new_miner(&miner_account, SEAL_PROOF).expect("Registration failed");
+        Power::update_claim(
+            miner_account,
+            PowerDelta::Increase(100),
+            PowerDelta::Increase(1000),
+        )
+        .expect("Update failed");
+        let first_claim = Claim {
+            raw_bytes_power: 100,
+            quality_adjusted_power: 1000,
+        };
+
+        // Changes are visible from the next block
+        assert_eq!(Power::claim_at(&miner_account, 1), None);
+        assert_eq!(Power::total_power_at(1), Some(Claim::default()));
+        assert_eq!(
+            Power::claim_history(miner_account),
+            vec![(1, first_claim.clone())]
+        );
+
+        System::set_block_number(12);
+        Power::update_claim(
+            miner_account,
+            PowerDelta::Increase(50),
+            PowerDelta::Increase(500),
+        )
+        .expect("Update failed");
+        let second_claim = Claim {
+            raw_bytes_power: 150,
+            quality_adjusted_power: 1500,
+        };
+        assert_eq!(
+            Power::claim_at(&miner_account, 12),
+            Some(first_claim.clone())
+        );
+        assert_eq!(Power::total_power_at(12), Some(first_claim.clone()));
+        // Future blocks are not retained yet
+        assert_eq!(Power::claim_at(&miner_account, 13), None);
+        assert_eq!(Power::total_power_at(13), None);
+        // Unknown miners have no claim
+        assert_eq!(Power::claim_at(&2, 12), None);
+
+        // Blocks without changes record nothing
+        System::set_block_number(50);
+        assert_eq!(Power::claim_history(miner_account).len(), 2);
+        assert_eq!(
+            Power::claim_at(&miner_account, 13),
+            Some(second_claim.clone())
+        );
+
+        // Only the last 30 blocks are retained, the latest change before them is kept
+        Power::update_claim(
+            miner_account,
+            PowerDelta::Decrease(50),
+            PowerDelta::Decrease(500),
+        )
+        .expect("Update failed");
+        assert_eq!(
+            Power::claim_history(miner_account),
+            vec![(12, second_claim.clone()), (50, first_claim.clone())]
+        );
+        assert_eq!(
+            Power::power_history(),
+            vec![(12, second_claim.clone()), (50, first_claim)]
+        );
+        assert_eq!(Power::claim_at(&miner_account, 19), None);
+        assert_eq!(Power::total_power_at(19), None);
+        assert_eq!(
+            Power::claim_at(&miner_account, 20),
+            Some(second_claim.clone())
+        );
+        assert_eq!(Power::total_power_at(50), Some(second_claim));
+    });
+}
+
+#[test]
+fn suspend_eligibility_excludes_miner_until_block() {
+    new_test_ext().execute_with(|| {
+        let miner_account: u64 = 1;
+        Power::register_new_miner(&miner_account, SEAL_PROOF).expect("Registration failed");
+        Power::update_claim(
+            miner_account,
+            PowerDelta::Increase(100),
+            PowerDelta::Increase(100),
+        )
+        .expect("Update failed");
+        System::set_block_number(5);
+        assert!(Power::is_eligible(&miner_account));
+
+        Power::suspend_eligibility(&miner_account, 10);
+        assert_eq!(Power::suspended_until(miner_account), Some(10));
+        assert!(!Power::is_eligible(&miner_account));
+
+        // A shorter suspension does not end the current one early
+        Power::suspend_eligibility(&miner_account, 8);
+        assert_eq!(Power::suspended_until(miner_account), Some(10));
+        System::set_block_number(9);
+        assert!(!Power::is_eligible(&miner_account));
+
+        System::set_block_number(10);
+        assert!(Power::is_eligible(&miner_account));
+    });
+}
+
+#[test]
+fn deregister_miner_removes_claim_without_power() {
+    new_test_ext().execute_with(|| {
+        let miner_account: u64 = 1;
+        System::set_block_number(1);
+        Power::register_new_miner(&miner_account, SEAL_PROOF).expect("Registration failed");
+        Power::register_new_miner(&2, SEAL_PROOF).expect("Registration failed");
+        Power::update_claim(
+            miner_account,
+            PowerDelta::Increase(100),
+            PowerDelta::Increase(100),
+        )
+        .expect("Update failed");
+        Power::suspend_eligibility(&miner_account, 10);
+
+        // Miners still claiming power and unknown miners can not be deregistered
+        assert_eq!(Power::deregister_miner(&miner_account), None);
+        assert_eq!(Power::deregister_miner(&3), None);
+        assert_eq!(Power::miner_count(), Some(2));
+
+        Power::update_claim(
+            miner_account,
+            PowerDelta::Decrease(100),
+            PowerDelta::Decrease(100),
+        )
+        .expect("Update failed");
+        assert_eq!(
+            Power::deregister_miner(&miner_account),
+            Some(Claim::default())
+        );
+        assert_eq!(Power::claims(miner_account), None);
+        assert!(Power::claim_history(miner_account).is_empty());
+        assert_eq!(Power::seal_proof_types(miner_account), None);
+        assert_eq!(Power::suspended_until(miner_account), None);
+        assert_eq!(Power::miner_count(), Some(1));
+        assert_eq!(
+            System::events().pop().map(|e| e.event),
+            Some(Event::pallet_power(pallet_power::Event::MinerDeregistered(
+                miner_account
+            )))
+        );
+
+        assert_eq!(Power::deregister_miner(&miner_account), None);
+        assert_eq!(Power::miner_count(), Some(1));
     });
 }
@@ -0,0 +1,250 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(test)]
+mod mock;
+
+// `pallet::Module` is created by `pallet` macro
+pub use pallet::{
+    Config, CumsumRealized, EffectiveNetworkTime, Event, Module, Pallet, ScheduledMinted,
+    ThisEpochReward, TotalMinted,
+};
+
+#[frame_support::pallet]
+pub mod pallet {
+    use frame_support::pallet_prelude::{
+        Get, Hooks, IsType, PhantomData, StorageValue, ValueQuery,
+    };
+    use frame_support::traits::{Currency, Imbalance};
+    use frame_support::weights::Weight;
+    use frame_system::pallet_prelude::BlockNumberFor;
+    use sp_runtime::helpers_128bit::multiply_by_rational;
+    use sp_runtime::traits::{CheckedDiv, One, SaturatedConversion, Saturating, Zero};
+    use sp_runtime::{FixedPointNumber, FixedU128};
+    use sp_std::vec::Vec;
+
+    use pallet_common::{
+        exp, exp_neg, ln, ln_2, AccountIdConversion, BlockProducers, Power, RewardDistributor,
+    };
+
+    pub type StoragePower<T> = <<T as Config>::Power as Power>::StoragePower;
+    pub type BalanceOf<T> =
+        <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config {
+        type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+        /// Currency the block rewards are minted in
+        type Currency: Currency<Self::AccountId>;
+        type Power: Power<AccountId = Self::AccountId, BlockNumber = Self::BlockNumber>;
+        /// Miners that produced the current block
+        type BlockProducers: BlockProducers;
+        /// Receives the rewards deposited in miner accounts, e.g. to vest them
+        type RewardDistributor: RewardDistributor<
+            AccountId = Self::AccountId,
+            Balance = BalanceOf<Self>,
+        >;
+        /// Total reward minted over time by the simple minting function, regardless of the
+        /// network power
+        type SimpleTotal: Get<BalanceOf<Self>>;
+        /// Total reward minted over time by the baseline minting function, as the network power
+        /// reaches the baseline
+        type BaselineTotal: Get<BalanceOf<Self>>;
+        /// Number of blocks in which half of the remaining reward of the minting functions is
+        /// minted
+        type MintingHalfLife: Get<BlockNumberFor<Self>>;
+        /// Raw bytes power the baseline starts from at genesis
+        type BaselineInitialValue: Get<StoragePower<Self>>;
+        /// Number of blocks in which the baseline doubles
+        type BaselineDoublingTime: Get<BlockNumberFor<Self>>;
+        /// Number of blocks before the current block the power of its producers is looked up
+        /// at, the lookback of the election they won
+        type PowerLookback: Get<BlockNumberFor<Self>>;
+        /// Maximum number of producers rewarded in a block, producers over it are not rewarded
+        type MaxBlockProducers: Get<u32>;
+    }
+
+    #[pallet::pallet]
+    #[pallet::generate_store(pub(super) trait Store)]
+    pub struct Pallet<T>(PhantomData<T>);
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        // Benchmark not accurate
+        // Note: Includes the weight of `on_finalize`, rewarding up to `MaxBlockProducers`
+        fn on_initialize(now: BlockNumberFor<T>) -> Weight {
+            Self::update_block_reward(now);
+            let max_producers = T::MaxBlockProducers::get() as u64;
+            T::DbWeight::get().reads_writes(6 + 4 * max_producers, 5 + 3 * max_producers)
+        }
+
+        // Benchmark not accurate
+        fn on_finalize(now: BlockNumberFor<T>) {
+            Self::award_block_reward(now);
+        }
+    }
+
+    /// Total rewards minted for miners
+    #[pallet::storage]
+    #[pallet::getter(fn total_minted)]
+    pub type TotalMinted<T: Config> = StorageValue<_, BalanceOf<T>, ValueQuery>;
+
+    /// Total rewards scheduled by the minting functions up to the current block, including
+    /// rewards of blocks without powered producers that were not minted
+    #[pallet::storage]
+    #[pallet::getter(fn scheduled_minted)]
+    pub type ScheduledMinted<T: Config> = StorageValue<_, BalanceOf<T>, ValueQuery>;
+
+    /// Reward of the current block
+    #[pallet::storage]
+    #[pallet::getter(fn this_epoch_reward)]
+    pub type ThisEpochReward<T: Config> = StorageValue<_, BalanceOf<T>, ValueQuery>;
+
+    /// Sum over all blocks of the network raw bytes power capped at the baseline
+    #[pallet::storage]
+    #[pallet::getter(fn cumsum_realized)]
+    pub type CumsumRealized<T: Config> = StorageValue<_, u128, ValueQuery>;
+
+    /// Number of blocks the baseline must have grown for to sum up to `CumsumRealized`, the time
+    /// the baseline minting function is evaluated at
+    #[pallet::storage]
+    #[pallet::getter(fn effective_network_time)]
+    pub type EffectiveNetworkTime<T: Config> = StorageValue<_, FixedU128, ValueQuery>;
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    #[pallet::metadata(T::AccountId = "AccountID", BalanceOf<T> = "Balance")]
+    pub enum Event<T: Config> {
+        /// Emits miner address and the reward deposited in it
+        RewardPaid(T::AccountId, BalanceOf<T>),
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {}
+
+    impl<T: Config> Pallet<T> {
+        /// Total reward of the simple minting function after `elapsed` blocks
+        pub fn simple_minted(elapsed: FixedU128) -> BalanceOf<T> {
+            Self::minted(T::SimpleTotal::get(), elapsed)
+        }
+
+        /// Total reward of the baseline minting function at `effective_network_time`
+        pub fn baseline_minted(effective_network_time: FixedU128) -> BalanceOf<T> {
+            Self::minted(T::BaselineTotal::get(), effective_network_time)
+        }
+
+        /// Baseline raw bytes power after `elapsed` blocks
+        pub fn baseline_power(elapsed: FixedU128) -> u128 {
+            exp(Self::growth_rate().saturating_mul(elapsed))
+                .saturating_mul_int(T::BaselineInitialValue::get().saturated_into::<u128>())
+        }
+
+        /// Updates the cumulative realized power and effective network time with the network
+        /// power at `now` and schedules the reward of the block
+        fn update_block_reward(now: BlockNumberFor<T>) {
+            // following updateToNextEpochWithReward in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/reward/reward_state.go
+            // Note: The effective network time is computed from the closed form of the sum of the
+            // baseline, b0 * (e^(g * t) - 1) / g, instead of being advanced one block at a time
+            let elapsed = FixedU128::saturating_from_integer(now.saturated_into::<u128>());
+            let realized_power = T::Power::total_power()
+                .raw_bytes_power
+                .saturated_into::<u128>()
+                .min(Self::baseline_power(elapsed));
+            let cumsum_realized = CumsumRealized::<T>::get().saturating_add(realized_power);
+
+            let baseline_initial = T::BaselineInitialValue::get().saturated_into::<u128>();
+            let growth_rate = Self::growth_rate();
+            let effective_network_time = if baseline_initial == 0 || growth_rate.is_zero() {
+                FixedU128::zero()
+            } else {
+                let baseline_sum =
+                    FixedU128::saturating_from_rational(cumsum_realized, baseline_initial)
+                        .saturating_mul(growth_rate);
+                ln(FixedU128::one().saturating_add(baseline_sum))
+                    .checked_div(&growth_rate)
+                    .unwrap_or_else(Zero::zero)
+            };
+
+            let scheduled = Self::simple_minted(elapsed)
+                .saturating_add(Self::baseline_minted(effective_network_time));
+            ThisEpochReward::<T>::put(scheduled.saturating_sub(ScheduledMinted::<T>::get()));
+            ScheduledMinted::<T>::put(scheduled);
+            CumsumRealized::<T>::put(cumsum_realized);
+            EffectiveNetworkTime::<T>::put(effective_network_time);
+        }
+
+        /// Reward minted by an exponential decay of `total` after `time` blocks
+        fn minted(total: BalanceOf<T>, time: FixedU128) -> BalanceOf<T> {
+            // following computeRTheta and computeReward in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/reward/reward_logic.go
+            FixedU128::one()
+                .saturating_sub(exp_neg(Self::decay_rate().saturating_mul(time)))
+                .saturating_mul_int(total.saturated_into::<u128>())
+                .saturated_into::<BalanceOf<T>>()
+        }
+
+        /// Per block decay rate of the minting functions, ln(2) / half life
+        fn decay_rate() -> FixedU128 {
+            Self::rate_for_period(T::MintingHalfLife::get())
+        }
+
+        /// Per block growth rate of the baseline, ln(2) / doubling time
+        fn growth_rate() -> FixedU128 {
+            Self::rate_for_period(T::BaselineDoublingTime::get())
+        }
+
+        fn rate_for_period(period: BlockNumberFor<T>) -> FixedU128 {
+            ln_2()
+                .checked_div(&FixedU128::saturating_from_integer(
+                    period.saturated_into::<u128>(),
+                ))
+                .unwrap_or_else(Zero::zero)
+        }
+
+        /// Mints the block reward and splits it between the producers of the block in
+        /// proportion to their quality adjusted power `PowerLookback` blocks before `now`
+        fn award_block_reward(now: BlockNumberFor<T>) {
+            // following AwardBlockReward in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/reward/reward_actor.go
+            // Note: Producers without power at the lookback block or over `MaxBlockProducers`
+            // are not rewarded and the rounding remainder of the shares is not minted
+            let lookback = now.saturating_sub(T::PowerLookback::get());
+            let producers: Vec<(T::AccountId, u128)> = T::BlockProducers::block_producers()
+                .iter()
+                .take(T::MaxBlockProducers::get() as usize)
+                .filter_map(|miner_id| {
+                    let miner: T::AccountId = miner_id.into_account();
+                    let power = T::Power::claim_at(&miner, lookback)?
+                        .quality_adjusted_power
+                        .saturated_into::<u128>();
+                    Some((miner, power))
+                })
+                .filter(|(_, power)| *power > 0)
+                .collect();
+            let total_power = producers
+                .iter()
+                .fold(0u128, |total, (_, power)| total.saturating_add(*power));
+            if total_power == 0 {
+                return;
+            }
+
+            let block_reward = ThisEpochReward::<T>::get().saturated_into::<u128>();
+            let mut minted = BalanceOf::<T>::zero();
+            for (miner, power) in producers {
+                let reward = multiply_by_rational(block_reward, power, total_power)
+                    .map(|reward| reward.saturated_into::<BalanceOf<T>>())
+                    .unwrap_or_else(|_| BalanceOf::<T>::zero());
+                // Nothing is deposited if the reward is below the existential deposit of a new
+                // account
+                let reward = T::Currency::deposit_creating(&miner, reward).peek();
+                if reward.is_zero() {
+                    continue;
+                }
+                T::RewardDistributor::distribute_reward(&miner, reward);
+                minted = minted.saturating_add(reward);
+                Self::deposit_event(Event::RewardPaid(miner, reward));
+            }
+            TotalMinted::<T>::mutate(|total| *total = total.saturating_add(minted));
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use crate as pallet_reward;
+use frame_support::parameter_types;
+use frame_system as system;
+use pallet_common::{BlockProducers, MinerId, RegisteredSealProof, RewardDistributor};
+use sp_core::H256;
+use sp_runtime::{
+    testing::Header,
+    traits::{BlakeTwo256, Convert, IdentityLookup},
+};
+use std::cell::RefCell;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+    pub enum Test where
+        Block = Block,
+        NodeBlock = Block,
+        UncheckedExtrinsic = UncheckedExtrinsic,
+        {
+            System: frame_system::{Module, Call, Config, Storage, Event<T>},
+            Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
+            Power: pallet_power::{Module, Storage, Event<T>},
+            Reward: pallet_reward::{Module, Call, Storage, Event<T>},
+        }
+);
+
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+    pub const SS58Prefix: u8 = 42;
+}
+
+// not sure where the Call, Event, PalletInfo comes from
+impl system::Config for Test {
+    type BaseCallFilter = ();
+    type BlockWeights = ();
+    type BlockLength = ();
+    type DbWeight = ();
+    type Origin = Origin;
+    type Call = Call;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = u64;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = Event;
+    type BlockHashCount = BlockHashCount;
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = pallet_balances::AccountData<u64>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = SS58Prefix;
+}
+
+parameter_types! {
+    pub const ExistentialDeposit: u64 = 1;
+    pub const MaxLocks: u32 = 50;
+}
+
+impl pallet_balances::Config for Test {
+    type MaxLocks = MaxLocks;
+    type Balance = u64;
+    type Event = Event;
+    type DustRemoval = ();
+    type ExistentialDeposit = ExistentialDeposit;
+    type AccountStore = System;
+    type WeightInfo = ();
+}
+
+/// Consensus minimum power of a single sector
+pub struct SectorSize;
+
+impl Convert<RegisteredSealProof, u128> for SectorSize {
+    fn convert(seal_proof: RegisteredSealProof) -> u128 {
+        seal_proof.sector_size() as u128
+    }
+}
+
+parameter_types! {
+    pub ConsensusMinerMinMiners: u64 = 3;
+    pub const PowerHistoryRetention: u64 = 30;
+}
+
+impl pallet_power::Config for Test {
+    type Event = Event;
+    type PeerId = Vec<u8>;
+    type StoragePower = u128;
+    type ConsensusMinerMinPower = SectorSize;
+    type ConsensusMinerMinMiners = ConsensusMinerMinMiners;
+    type PowerHistoryRetention = PowerHistoryRetention;
+}
+
+thread_local! {
+    static BLOCK_PRODUCERS: RefCell<Vec<u32>> = RefCell::new(Vec::new());
+    static DISTRIBUTED_REWARDS: RefCell<Vec<(u64, u64)>> = RefCell::new(Vec::new());
+}
+
+/// Sets the miners returned by `MockBlockProducers` from their index
+pub fn set_block_producers(miners: Vec<u32>) {
+    BLOCK_PRODUCERS.with(|producers| *producers.borrow_mut() = miners);
+}
+
+/// Rewards received by `MockRewardDistributor`
+pub fn distributed_rewards() -> Vec<(u64, u64)> {
+    DISTRIBUTED_REWARDS.with(|rewards| rewards.borrow().clone())
+}
+
+pub struct MockBlockProducers;
+
+impl BlockProducers for MockBlockProducers {
+    fn block_producers() -> Vec<MinerId> {
+        BLOCK_PRODUCERS.with(|producers| producers.borrow().iter().map(|i| MinerId(*i)).collect())
+    }
+}
+
+pub struct MockRewardDistributor;
+
+impl RewardDistributor for MockRewardDistributor {
+    type AccountId = u64;
+    type Balance = u64;
+
+    fn distribute_reward(miner: &u64, reward: u64) {
+        DISTRIBUTED_REWARDS.with(|rewards| rewards.borrow_mut().push((*miner, reward)));
+    }
+}
+
+parameter_types! {
+    pub SimpleTotal: u64 = 1_000_000_000;
+    pub BaselineTotal: u64 = 2_000_000_000;
+    pub MintingHalfLife: u64 = 100;
+    pub BaselineInitialValue: u128 = 1_000_000;
+    pub BaselineDoublingTime: u64 = 50;
+    pub PowerLookback: u64 = 10;
+    pub MaxBlockProducers: u32 = 3;
+}
+
+impl pallet_reward::Config for Test {
+    type Event = Event;
+    type Currency = Balances;
+    type Power = Power;
+    type BlockProducers = MockBlockProducers;
+    type RewardDistributor = MockRewardDistributor;
+    type SimpleTotal = SimpleTotal;
+    type BaselineTotal = BaselineTotal;
+    type MintingHalfLife = MintingHalfLife;
+    type BaselineInitialValue = BaselineInitialValue;
+    type BaselineDoublingTime = BaselineDoublingTime;
+    type PowerLookback = PowerLookback;
+    type MaxBlockProducers = MaxBlockProducers;
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    system::GenesisConfig::default()
+        .build_storage::<Test>()
+        .unwrap()
+        .into()
+}
@@ -0,0 +1,224 @@
+// Imports created by construct_runtime macros are unresolved by rust analyzer
+use crate as pallet_reward;
+use crate::mock::{
+    distributed_rewards, new_test_ext, set_block_producers, Balances, Event, Power, Reward, System,
+};
+use frame_support::traits::{Currency, OnFinalize, OnInitialize};
+use pallet_common::{
+    AccountIdConversion, MinerId, Power as PowerTrait, PowerDelta, RegisteredSealProof,
+};
+use sp_runtime::{FixedPointNumber, FixedU128};
+
+// Cumulative rewards scheduled at a block for the mock parameters, computed with 60 significant
+// digits from S * (1 - e^(-λt)) + B * (1 - e^(-λθ)) with θ = ln(1 + g * Σ min(p, b(i)) / b0) / g
+// and b(i) = floor(b0 * e^(g * i))
+const BLOCKS: [u64; 7] = [1, 10, 50, 100, 200, 500, 1_000];
+// Without power only the simple minting function mints
+const MINTED_WITHOUT_POWER: [u64; 7] = [
+    6_907_504,
+    66_967_008,
+    292_893_218,
+    500_000_000,
+    750_000_000,
+    968_750_000,
+    999_023_437,
+];
+// A constant power of 2_000_000 is reached by the baseline at block 50
+const MINTED_WITH_CONSTANT_POWER: [u64; 7] = [
+    20_817_487,
+    201_739_526,
+    881_129_454,
+    1_414_268_065,
+    1_944_558_105,
+    2_443_226_926,
+    2_623_376_400,
+];
+// The power stays above the baseline, so the effective network time follows the block number
+const MINTED_ABOVE_BASELINE: [u64; 7] = [
+    20_817_487,
+    201_739_526,
+    881_129_454,
+    1_502_595_082,
+    2_251_620_384,
+    2_906_465_774,
+    2_997_075_674,
+];
+
+// Utility functions
+//
+// Registers a miner in pallet-power with `power` as raw bytes and quality adjusted power, rewards
+// use it from block 11 on, when the lookback block is after the registration
+fn register_miner(index: u32, power: u128) -> u64 {
+    let miner: u64 = MinerId(index).into_account();
+    Power::register_new_miner(&miner, RegisteredSealProof::StackedDrg2KiBV1)
+        .expect("Registration failed");
+    Power::update_claim(
+        miner,
+        PowerDelta::Increase(power),
+        PowerDelta::Increase(power),
+    )
+    .expect("Update failed");
+    miner
+}
+
+fn run_to_block(n: u64) {
+    while System::block_number() < n {
+        let block = System::block_number() + 1;
+        System::set_block_number(block);
+        Reward::on_initialize(block);
+        Reward::on_finalize(block);
+    }
+}
+
+// Fixed point rounding errors are accepted up to a relative 1e-8
+fn assert_minted_table(expected: &[u64; 7]) {
+    for (block, minted) in BLOCKS.iter().zip(expected.iter()) {
+        run_to_block(*block);
+        let scheduled = Reward::scheduled_minted();
+        let difference = scheduled.max(*minted) - scheduled.min(*minted);
+        assert!(
+            difference <= minted / 100_000_000 + 1,
+            "scheduled {} at block {}, expected {}",
+            scheduled,
+            block,
+            minted
+        );
+    }
+}
+
+#[test]
+fn block_reward_is_split_by_power() {
+    new_test_ext().execute_with(|| {
+        let first_miner = register_miner(1, 100);
+        let second_miner = register_miner(2, 300);
+        set_block_producers(vec![1, 2]);
+
+        // Producers are rewarded with their power at the lookback block
+        run_to_block(10);
+        assert_eq!(Balances::total_issuance(), 0);
+        Power::update_claim(
+            first_miner,
+            PowerDelta::Increase(200),
+            PowerDelta::Increase(200),
+        )
+        .expect("Update failed");
+        run_to_block(11);
+
+        let reward = Reward::this_epoch_reward();
+        assert!(reward > 0);
+        assert_eq!(Balances::free_balance(first_miner), reward / 4);
+        assert_eq!(Balances::free_balance(second_miner), reward * 3 / 4);
+        assert_eq!(Balances::total_issuance(), reward / 4 + reward * 3 / 4);
+        assert_eq!(Reward::total_minted(), Balances::total_issuance());
+        assert_eq!(
+            distributed_rewards(),
+            vec![(first_miner, reward / 4), (second_miner, reward * 3 / 4)]
+        );
+        assert_eq!(
+            System::events().pop().map(|e| e.event),
+            Some(Event::pallet_reward(pallet_reward::Event::RewardPaid(
+                second_miner,
+                reward * 3 / 4
+            )))
+        );
+    });
+}
+
+#[test]
+fn producers_without_power_are_not_rewarded() {
+    new_test_ext().execute_with(|| {
+        let miner = register_miner(1, 100);
+        let miner_without_power = register_miner(2, 0);
+        // Miner 3 is not registered
+        set_block_producers(vec![1, 2, 3]);
+
+        run_to_block(11);
+
+        assert_eq!(Balances::free_balance(miner), Reward::this_epoch_reward());
+        assert_eq!(Balances::free_balance(miner_without_power), 0);
+        assert_eq!(Reward::total_minted(), Reward::this_epoch_reward());
+    });
+}
+
+#[test]
+fn producers_over_the_maximum_are_not_rewarded() {
+    new_test_ext().execute_with(|| {
+        let miners: Vec<u64> = (1..5).map(|index| register_miner(index, 100)).collect();
+        set_block_producers(vec![1, 2, 3, 4]);
+
+        run_to_block(11);
+
+        let reward = Reward::this_epoch_reward();
+        for miner in &miners[..3] {
+            assert_eq!(Balances::free_balance(miner), reward / 3);
+        }
+        assert_eq!(Balances::free_balance(miners[3]), 0);
+        assert_eq!(distributed_rewards().len(), 3);
+    });
+}
+
+#[test]
+fn nothing_is_minted_without_powered_producers() {
+    new_test_ext().execute_with(|| {
+        register_miner(1, 0);
+
+        run_to_block(11);
+        set_block_producers(vec![1]);
+        run_to_block(12);
+
+        assert_eq!(Balances::total_issuance(), 0);
+        assert_eq!(Reward::total_minted(), 0);
+        assert!(Reward::scheduled_minted() > 0);
+        assert!(distributed_rewards().is_empty());
+    });
+}
+
+#[test]
+fn block_rewards_sum_up_to_scheduled_rewards() {
+    new_test_ext().execute_with(|| {
+        register_miner(1, 2_000_000);
+        set_block_producers(vec![1]);
+
+        // The producer has no power at the lookback block of the first 10 blocks
+        run_to_block(10);
+        assert_eq!(Reward::total_minted(), 0);
+        let unminted = Reward::scheduled_minted();
+
+        run_to_block(100);
+
+        assert_eq!(
+            Reward::total_minted(),
+            Reward::scheduled_minted() - unminted
+        );
+        assert_eq!(Balances::total_issuance(), Reward::total_minted());
+    });
+}
+
+#[test]
+fn simple_minting_matches_reference_table() {
+    new_test_ext().execute_with(|| {
+        assert_minted_table(&MINTED_WITHOUT_POWER);
+        assert_eq!(Reward::cumsum_realized(), 0);
+    });
+}
+
+#[test]
+fn baseline_minting_matches_reference_table() {
+    new_test_ext().execute_with(|| {
+        register_miner(1, 2_000_000);
+        assert_minted_table(&MINTED_WITH_CONSTANT_POWER);
+    });
+}
+
+#[test]
+fn baseline_minting_above_baseline_matches_reference_table() {
+    new_test_ext().execute_with(|| {
+        register_miner(1, 1_000_000_000_000);
+        assert_minted_table(&MINTED_ABOVE_BASELINE);
+
+        // The realized power is capped at the baseline
+        let effective_network_time = Reward::effective_network_time();
+        assert!(effective_network_time > FixedU128::saturating_from_integer(1_000));
+        assert!(effective_network_time < FixedU128::saturating_from_integer(1_001));
+    });
+}
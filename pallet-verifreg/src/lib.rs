@@ -0,0 +1,207 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(test)]
+mod mock;
+
+// `pallet::Module` is created by `pallet` macro
+pub use pallet::{Config, Error, Event, Module, Pallet, VerifiedClients, Verifiers};
+
+#[frame_support::pallet]
+pub mod pallet {
+    use frame_support::pallet_prelude::{
+        ensure, Blake2_128Concat, DispatchResult, DispatchResultWithPostInfo, EnsureOrigin, Get,
+        Hooks, IsType, PhantomData, StorageMap,
+    };
+    use frame_system::pallet_prelude::{ensure_signed, BlockNumberFor, OriginFor};
+
+    use pallet_common::{DataCap, VerifiedRegistry};
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config {
+        type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+        /// Origin of the root key adding and removing verifiers
+        type RootOrigin: EnsureOrigin<Self::Origin>;
+        /// Minimum size of a verified deal, allowances and DataCaps can not be smaller
+        type MinVerifiedDealSize: Get<DataCap>;
+    }
+
+    #[pallet::pallet]
+    #[pallet::generate_store(pub(super) trait Store)]
+    pub struct Pallet<T>(PhantomData<T>);
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+
+    /// Allowance of verifiers, the DataCap they can still grant to clients
+    #[pallet::storage]
+    #[pallet::getter(fn verifiers)]
+    pub type Verifiers<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, DataCap>;
+
+    /// DataCap of verified clients, the bytes they can still store in verified deals
+    #[pallet::storage]
+    #[pallet::getter(fn verified_clients)]
+    pub type VerifiedClients<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, DataCap>;
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    #[pallet::metadata(T::AccountId = "AccountID")]
+    pub enum Event<T: Config> {
+        /// Emits verifier address and its allowance
+        VerifierAdded(T::AccountId, DataCap),
+        /// Emits verifier address
+        VerifierRemoved(T::AccountId),
+        /// Emits verifier address, client address and the DataCap granted to the client
+        VerifiedClientAdded(T::AccountId, T::AccountId, DataCap),
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        AllowanceBelowMinimum,
+        InvalidVerifier,
+        InvalidClient,
+        NoSuchVerifier,
+        InsufficientAllowance,
+        VerifiedClientExists,
+        NoSuchVerifiedClient,
+        DealTooSmall,
+        InsufficientDataCap,
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        // Benchmark not accurate
+        #[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+        pub fn add_verifier(
+            origin: OriginFor<T>,
+            verifier: T::AccountId,
+            allowance: DataCap,
+        ) -> DispatchResultWithPostInfo {
+            // following AddVerifier in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/verifreg/verified_registry_actor.go
+            // Note: Adding an existing verifier replaces its allowance
+            T::RootOrigin::ensure_origin(origin)?;
+            ensure!(
+                allowance >= T::MinVerifiedDealSize::get(),
+                Error::<T>::AllowanceBelowMinimum
+            );
+            ensure!(
+                !VerifiedClients::<T>::contains_key(&verifier),
+                Error::<T>::InvalidVerifier
+            );
+            Verifiers::<T>::insert(&verifier, allowance);
+            Self::deposit_event(Event::VerifierAdded(verifier, allowance));
+
+            Ok(().into())
+        }
+
+        // Benchmark not accurate
+        #[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+        pub fn remove_verifier(
+            origin: OriginFor<T>,
+            verifier: T::AccountId,
+        ) -> DispatchResultWithPostInfo {
+            // following RemoveVerifier in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/verifreg/verified_registry_actor.go
+            T::RootOrigin::ensure_origin(origin)?;
+            ensure!(
+                Verifiers::<T>::contains_key(&verifier),
+                Error::<T>::NoSuchVerifier
+            );
+            Verifiers::<T>::remove(&verifier);
+            Self::deposit_event(Event::VerifierRemoved(verifier));
+
+            Ok(().into())
+        }
+
+        // Benchmark not accurate
+        #[pallet::weight(10_000 + T::DbWeight::get().writes(2))]
+        pub fn add_verified_client(
+            origin: OriginFor<T>,
+            client: T::AccountId,
+            allowance: DataCap,
+        ) -> DispatchResultWithPostInfo {
+            // following AddVerifiedClient in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/verifreg/verified_registry_actor.go
+            let verifier = ensure_signed(origin)?;
+            ensure!(
+                allowance >= T::MinVerifiedDealSize::get(),
+                Error::<T>::AllowanceBelowMinimum
+            );
+            ensure!(
+                !Verifiers::<T>::contains_key(&client),
+                Error::<T>::InvalidClient
+            );
+            let verifier_allowance =
+                Verifiers::<T>::get(&verifier).ok_or(Error::<T>::NoSuchVerifier)?;
+            ensure!(
+                verifier_allowance >= allowance,
+                Error::<T>::InsufficientAllowance
+            );
+            ensure!(
+                !VerifiedClients::<T>::contains_key(&client),
+                Error::<T>::VerifiedClientExists
+            );
+
+            Verifiers::<T>::insert(&verifier, verifier_allowance - allowance);
+            VerifiedClients::<T>::insert(&client, allowance);
+            Self::deposit_event(Event::VerifiedClientAdded(verifier, client, allowance));
+
+            Ok(().into())
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        /// Total size of verified deals
+        fn total_size(deal_sizes: &[DataCap]) -> DataCap {
+            deal_sizes
+                .iter()
+                .fold(0, |total, deal_size| total.saturating_add(*deal_size))
+        }
+    }
+
+    impl<T: Config> VerifiedRegistry for Pallet<T> {
+        type AccountId = T::AccountId;
+
+        fn can_use_bytes(client: &T::AccountId, deal_sizes: &[DataCap]) -> DispatchResult {
+            // following UseBytes in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/verifreg/verified_registry_actor.go
+            let data_cap =
+                VerifiedClients::<T>::get(client).ok_or(Error::<T>::NoSuchVerifiedClient)?;
+            ensure!(
+                deal_sizes
+                    .iter()
+                    .all(|deal_size| *deal_size >= T::MinVerifiedDealSize::get()),
+                Error::<T>::DealTooSmall
+            );
+            let total_size = Self::total_size(deal_sizes);
+            ensure!(total_size <= data_cap, Error::<T>::InsufficientDataCap);
+            Ok(())
+        }
+
+        fn use_bytes(client: &T::AccountId, deal_sizes: &[DataCap]) {
+            // following UseBytes, clients left with less than a verified deal of DataCap are
+            // removed
+            let total_size = Self::total_size(deal_sizes);
+            VerifiedClients::<T>::mutate_exists(client, |maybe_data_cap| {
+                if let Some(data_cap) = maybe_data_cap {
+                    let remaining = data_cap.saturating_sub(total_size);
+                    *maybe_data_cap = if remaining < T::MinVerifiedDealSize::get() {
+                        None
+                    } else {
+                        Some(remaining)
+                    };
+                }
+            });
+        }
+
+        fn restore_bytes(client: &T::AccountId, deal_size: DataCap) {
+            // following RestoreBytes in https://github.com/filecoin-project/specs-actors/blob/57195d8909b1c366fd1af41de9e92e11d7876177/actors/builtin/verifreg/verified_registry_actor.go
+            // Note: Verifiers can not be verified clients, their DataCap is not restored
+            if Verifiers::<T>::contains_key(client) {
+                return;
+            }
+            VerifiedClients::<T>::mutate(client, |maybe_data_cap| {
+                *maybe_data_cap = Some(maybe_data_cap.unwrap_or(0).saturating_add(deal_size));
+            });
+        }
+    }
+}
@@ -0,0 +1,139 @@
+// Imports created by construct_runtime macros are unresolved by rust analyzer
+use crate as pallet_verifreg;
+use crate::mock::{new_test_ext, Event, Origin, System, Test, VerifReg};
+use crate::Error;
+use frame_support::{assert_noop, assert_ok};
+use pallet_common::VerifiedRegistry;
+use sp_runtime::DispatchError;
+
+const VERIFIER: u64 = 1;
+const CLIENT: u64 = 2;
+
+// Utility functions
+//
+// Adds `VERIFIER` with an allowance of 10_240 and grants `CLIENT` a DataCap of 4_096
+fn add_verified_client() {
+    assert_ok!(VerifReg::add_verifier(Origin::root(), VERIFIER, 10_240));
+    assert_ok!(VerifReg::add_verified_client(
+        Origin::signed(VERIFIER),
+        CLIENT,
+        4_096
+    ));
+}
+
+#[test]
+fn add_verifier_requires_root() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        assert_noop!(
+            VerifReg::add_verifier(Origin::signed(VERIFIER), VERIFIER, 10_240),
+            DispatchError::BadOrigin
+        );
+        assert_noop!(
+            VerifReg::add_verifier(Origin::root(), VERIFIER, 1_000),
+            Error::<Test>::AllowanceBelowMinimum
+        );
+
+        assert_ok!(VerifReg::add_verifier(Origin::root(), VERIFIER, 10_240));
+        assert_eq!(VerifReg::verifiers(VERIFIER), Some(10_240));
+        assert_eq!(
+            System::events().pop().map(|e| e.event),
+            Some(Event::pallet_verifreg(
+                pallet_verifreg::Event::VerifierAdded(VERIFIER, 10_240)
+            ))
+        );
+
+        assert_noop!(
+            VerifReg::remove_verifier(Origin::signed(VERIFIER), VERIFIER),
+            DispatchError::BadOrigin
+        );
+        assert_ok!(VerifReg::remove_verifier(Origin::root(), VERIFIER));
+        assert_eq!(VerifReg::verifiers(VERIFIER), None);
+        assert_noop!(
+            VerifReg::remove_verifier(Origin::root(), VERIFIER),
+            Error::<Test>::NoSuchVerifier
+        );
+    });
+}
+
+#[test]
+fn add_verified_client_consumes_verifier_allowance() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        add_verified_client();
+
+        assert_eq!(VerifReg::verifiers(VERIFIER), Some(6_144));
+        assert_eq!(VerifReg::verified_clients(CLIENT), Some(4_096));
+        assert_eq!(
+            System::events().pop().map(|e| e.event),
+            Some(Event::pallet_verifreg(
+                pallet_verifreg::Event::VerifiedClientAdded(VERIFIER, CLIENT, 4_096)
+            ))
+        );
+    });
+}
+
+#[test]
+fn add_verified_client_rejects_invalid_clients() {
+    new_test_ext().execute_with(|| {
+        add_verified_client();
+
+        assert_noop!(
+            VerifReg::add_verified_client(Origin::signed(CLIENT), 3, 4_096),
+            Error::<Test>::NoSuchVerifier
+        );
+        assert_noop!(
+            VerifReg::add_verified_client(Origin::signed(VERIFIER), 3, 1_000),
+            Error::<Test>::AllowanceBelowMinimum
+        );
+        assert_noop!(
+            VerifReg::add_verified_client(Origin::signed(VERIFIER), 3, 8_192),
+            Error::<Test>::InsufficientAllowance
+        );
+        assert_noop!(
+            VerifReg::add_verified_client(Origin::signed(VERIFIER), CLIENT, 1_024),
+            Error::<Test>::VerifiedClientExists
+        );
+        assert_noop!(
+            VerifReg::add_verified_client(Origin::signed(VERIFIER), VERIFIER, 1_024),
+            Error::<Test>::InvalidClient
+        );
+        assert_noop!(
+            VerifReg::add_verifier(Origin::root(), CLIENT, 10_240),
+            Error::<Test>::InvalidVerifier
+        );
+    });
+}
+
+#[test]
+fn use_bytes_consumes_data_cap() {
+    new_test_ext().execute_with(|| {
+        add_verified_client();
+
+        assert_eq!(
+            VerifReg::can_use_bytes(&3, &[1_024]),
+            Err(Error::<Test>::NoSuchVerifiedClient.into())
+        );
+        assert_eq!(
+            VerifReg::can_use_bytes(&CLIENT, &[512]),
+            Err(Error::<Test>::DealTooSmall.into())
+        );
+        assert_eq!(
+            VerifReg::can_use_bytes(&CLIENT, &[2_048, 4_096]),
+            Err(Error::<Test>::InsufficientDataCap.into())
+        );
+        assert_eq!(VerifReg::can_use_bytes(&CLIENT, &[1_024, 2_048]), Ok(()));
+
+        VerifReg::use_bytes(&CLIENT, &[1_024, 2_048]);
+        assert_eq!(VerifReg::verified_clients(CLIENT), Some(1_024));
+
+        // Clients left with less than the minimum deal size are removed
+        VerifReg::use_bytes(&CLIENT, &[1_024]);
+        assert_eq!(VerifReg::verified_clients(CLIENT), None);
+
+        VerifReg::restore_bytes(&CLIENT, 2_048);
+        assert_eq!(VerifReg::verified_clients(CLIENT), Some(2_048));
+        VerifReg::restore_bytes(&VERIFIER, 2_048);
+        assert_eq!(VerifReg::verified_clients(VERIFIER), None);
+    });
+}